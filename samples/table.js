@@ -0,0 +1,10 @@
+function pad(text, width) {
+    while (text.length < width) {
+        text = " " + text;
+    }
+    return text;
+}
+
+for (var i = 1; i <= 5; i++) {
+    __console_log(pad("" + i, 2) + " squared is " + pad("" + i * i, 2));
+}
@@ -1,8 +1,9 @@
+mod condition;
 pub mod gdb_jit;
 
 use std::io::Write;
 
-use almond::ast::BinaryOperator;
+use almond::ast::{BinaryOperator, UnaryOperator};
 use anyhow::Result;
 use iced_x86::{
     code_asm::{AsmRegister64, *},
@@ -14,22 +15,60 @@ use crate::{
     environment::{Environment, TypeInfo},
     flow_graph::BasicBlockId,
 };
-use crate::{flow_graph::{FlowInstruction, TailInstruction}, Id, Value};
+use crate::{
+    flow_graph::{CallArguments, FlowInstruction, TailInstruction},
+    inline_cache::{
+        InlineCache, PropertyAccess, INLINE_CACHE_ENTRIES, INLINE_CACHE_ENTRY_SIZE,
+        INLINE_CACHE_HITS_OFFSET, INLINE_CACHE_SLOT_OFFSET,
+    },
+    nan_boxing::{
+        box_number, JsValue, FALSE, FIRST_TAG, NULL, PAYLOAD_MASK, TAG_FUNCTION, TAG_OBJECT, TRUE,
+        UNDEFINED, UNINITIALIZED,
+    },
+    object::{Object, ShapeId, OBJECT_SHAPE_OFFSET, OBJECT_SLOTS_OFFSET},
+    string::{allocate_string, intern_string, string_value},
+    Id, Value,
+};
 
-use self::gdb_jit::GdbJitImageRegistration;
+use self::{condition::Condition, gdb_jit::GdbJitImageRegistration};
 
 pub type UnaryFunction = extern "win64" fn() -> ();
 
 pub struct CodegenContext {
     pub stack: Vec<CodegenStackEntry>,
+
+    /// Set while the processor flags hold the outcome of the comparison
+    /// whose result is on top of the stack, so a branch on that result can
+    /// jump on the flags directly.
+    pub comparison: Option<Condition>,
 }
 
 const ARGUMENT_REGISTERS: [AsmRegister64; 4] = [rcx, rdx, r8, r9];
+
+/// The number of arguments passed in registers.
+pub(crate) const REGISTER_ARGUMENT_COUNT: usize = ARGUMENT_REGISTERS.len();
+
+/// Holds the number of arguments passed to a JavaScript function, since its
+/// parameters are undefined unless there's an argument for them.
+const ARGUMENT_COUNT_REGISTER: AsmRegister64 = rsi;
+
 const VOLATILE_REGISTERS: [AsmRegister64; 7] = [rbx, r10, r11, r12, r13, r14, r15];
+const VOLATILE_DWORD_REGISTERS: [AsmRegister32; 7] = [ebx, r10d, r11d, r12d, r13d, r14d, r15d];
+const VOLATILE_BYTE_REGISTERS: [AsmRegister8; 7] = [bl, r10b, r11b, r12b, r13b, r14b, r15b];
+
+/// The offsets of the fields of a function object.
+const FUNCTION_CODE_OFFSET: usize = 0;
+const FUNCTION_CONTEXT_OFFSET: usize = 8;
+
+/// Executable code is mapped in whole pages.
+const PAGE_SIZE: usize = 4096;
 
 impl CodegenContext {
     pub fn new() -> Self {
-        Self { stack: Vec::new() }
+        Self {
+            stack: Vec::new(),
+            comparison: None,
+        }
     }
 
     pub fn current_stack_register(&self) -> AsmRegister64 {
@@ -45,12 +84,55 @@ impl CodegenContext {
         register
     }
 
+    fn stack_register_index(&self, register: AsmRegister64) -> usize {
+        VOLATILE_REGISTERS
+            .iter()
+            .position(|candidate| *candidate == register)
+            .expect("not a stack register")
+    }
+
+    /// The low byte of one of the registers backing the stack.
+    pub fn byte_register(&self, register: AsmRegister64) -> AsmRegister8 {
+        VOLATILE_BYTE_REGISTERS[self.stack_register_index(register)]
+    }
+
+    /// The low 32 bits of one of the registers backing the stack.
+    pub fn dword_register(&self, register: AsmRegister64) -> AsmRegister32 {
+        VOLATILE_DWORD_REGISTERS[self.stack_register_index(register)]
+    }
+
     pub fn argument_register(&self, index: usize) -> AsmRegister64 {
         *ARGUMENT_REGISTERS
             .get(index)
             .expect("argument register overflow")
     }
 
+    /// The offset from `rsp` of the slot that the function prologue spills
+    /// argument register `index` into. Parameters are read from there, since
+    /// the argument registers are overwritten by any call the function makes.
+    /// The arguments past those are passed in the caller's frame, above the
+    /// return address and the `frame_size` bytes of the callee's frame.
+    pub fn parameter_offset(&self, index: usize, frame_size: usize) -> usize {
+        match index.checked_sub(ARGUMENT_REGISTERS.len()) {
+            None => 8 * (VOLATILE_REGISTERS.len() + index),
+            Some(stack_index) => frame_size + 8 + 8 * stack_index,
+        }
+    }
+
+    /// The offset from `rsp` of the slot holding the innermost environment
+    /// record visible in the function: its own, if it has captured
+    /// variables, or the one it was called with. Callers pass it in `rax`.
+    pub fn context_offset(&self) -> usize {
+        8 * (VOLATILE_REGISTERS.len() + ARGUMENT_REGISTERS.len())
+    }
+
+    /// The offset from `rsp` of the slot that the function prologue saves
+    /// the argument count register into, which is also where the caller's
+    /// value of it is restored from.
+    pub fn argument_count_offset(&self) -> usize {
+        self.context_offset() + 8
+    }
+
     pub(crate) fn pop(&mut self) -> (CodegenStackEntry, AsmRegister64) {
         let entry = self.stack.pop().expect("stack underflow");
         let register = self.current_stack_register();
@@ -58,18 +140,51 @@ impl CodegenContext {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum CodegenStackEntry {
     Boolean,
     String,
     Number,
     Null,
+    Undefined,
 
     StackVariable(usize),
+    FunctionParameter(usize),
+    CapturedVariable {
+        depth: usize,
+        slot: usize,
+    },
+    Function,
+
+    /// An object whose shape is known, such as one just created from a
+    /// literal.
+    Object(ShapeId),
+
+    /// A value of any type, such as the result of a call.
+    Dynamic,
 
     Id(Id),
 }
 
+impl CodegenStackEntry {
+    pub fn is_number(&self) -> bool {
+        *self == CodegenStackEntry::Number
+    }
+
+    /// Whether the type of the entry's value is only known at runtime, from
+    /// its tag. Variables and parameters aren't typed, so they hold values
+    /// of any type.
+    pub fn is_dynamic(&self) -> bool {
+        matches!(
+            self,
+            CodegenStackEntry::Dynamic
+                | CodegenStackEntry::StackVariable(_)
+                | CodegenStackEntry::FunctionParameter(_)
+                | CodegenStackEntry::CapturedVariable { .. }
+        )
+    }
+}
+
 pub fn print_disassembled_code(bytes: &[u8], base_address: u64) -> String {
     const HEXBYTES_COLUMN_BYTE_LENGTH: usize = 10;
     let mut decoder = Decoder::with_ip(64, bytes, base_address, DecoderOptions::NONE);
@@ -125,36 +240,54 @@ pub fn print_disassembled_code(bytes: &[u8], base_address: u64) -> String {
     output
 }
 
+/// Generates a stub that compiles a basic block on first use and then
+/// transfers control to it.
+///
+/// Blocks are entered both by calls (function bodies) and by jumps from other
+/// blocks of the same function, so the stub has to be invisible to the code
+/// on either side: it preserves every register that may carry arguments or
+/// values on the codegen stack, and leaves `rsp` exactly as it found it
+/// before jumping to the compiled block.
 pub fn codegen_trampoline(
     environment: &mut Environment,
     basic_block_id: &BasicBlockId,
+    type_info: &TypeInfo,
     dump_disassembly: bool,
 ) -> Result<(GdbJitImageRegistration, UnaryFunction)> {
+    const PRESERVED_REGISTERS: [AsmRegister64; 7] = [rax, rcx, rdx, r8, r9, r10, r11];
+    const SHADOW_SPACE: usize = 4 * 8;
+
     let environment_ptr = environment as *mut Environment;
     let mut asm = CodeAssembler::new(64)?;
 
-    let stack_size = 3 * 8;
-
-    // asm.int3()?;
-    asm.sub(rsp, stack_size)?;
+    // reserve a slot for the address of the compiled block, which the final
+    // `ret` jumps to
+    asm.sub(rsp, 8)?;
+    asm.push(rbp)?;
+    asm.mov(rbp, rsp)?;
 
-    let arg0 = rsp + 0i8;
-    let arg1 = rsp + 8i8;
+    // the stub can be entered with any stack alignment
+    asm.and(rsp, -16)?;
+    let save_area = SHADOW_SPACE + 8 * PRESERVED_REGISTERS.len();
+    asm.sub(rsp, save_area.next_multiple_of(16) as i32)?;
 
-    asm.mov(arg0, rcx)?;
-    asm.mov(arg1, rdx)?;
+    for (index, register) in PRESERVED_REGISTERS.iter().enumerate() {
+        asm.mov(rsp + (SHADOW_SPACE + index * 8), *register)?;
+    }
 
     // get a pointer to the actual code
     asm.mov(rcx, environment_ptr as u64)?;
     asm.mov(rdx, basic_block_id.0 as u64)?;
-    asm.call(basic_block_trampoline as u64)?;
+    asm.mov(r8, type_info as *const TypeInfo as u64)?;
+    asm.call(basic_block_trampoline as *const () as u64)?;
+    asm.mov(rbp + 8, rax)?;
 
-    // restore arguments and call generated function
-    asm.mov(rcx, arg0)?;
-    asm.mov(rdx, arg1)?;
-    asm.call(rax)?;
+    for (index, register) in PRESERVED_REGISTERS.iter().enumerate() {
+        asm.mov(*register, rsp + (SHADOW_SPACE + index * 8))?;
+    }
 
-    asm.add(rsp, stack_size)?;
+    asm.mov(rsp, rbp)?;
+    asm.pop(rbp)?;
     asm.ret()?;
 
     let (base_address, registration, size) = assemble_code(asm)?;
@@ -170,42 +303,65 @@ pub fn codegen_trampoline(
 }
 
 fn assemble_code(mut asm: CodeAssembler) -> Result<(u64, GdbJitImageRegistration, usize)> {
-    let mut mmap = memmap::MmapMut::map_anon(4096)?;
+    let (mmap, size) = assemble_into_mapping(&mut asm)?;
     let base_address = mmap.as_ptr() as u64;
-    let instructions = asm.assemble(base_address)?;
-    let size = instructions.len();
-    (&mut mmap[..]).write_all(&instructions)?;
-    let mmap = mmap.make_exec()?;
 
     let registration = GdbJitImageRegistration::register(mmap);
 
     Ok((base_address, registration, size))
 }
 
+/// Assembles code into an executable mapping of its own, and returns the
+/// mapping with the size of the code. Calls and jumps to absolute addresses
+/// are encoded relative to where the code ends up, so its size is only
+/// known once it's been assembled there; if it doesn't fit, it's assembled
+/// again into a mapping of the size it came out as.
+fn assemble_into_mapping(asm: &mut CodeAssembler) -> Result<(Mmap, usize)> {
+    let mut capacity = PAGE_SIZE;
+
+    loop {
+        let mut mmap = memmap::MmapMut::map_anon(capacity)?;
+        let instructions = asm.assemble(mmap.as_ptr() as u64)?;
+
+        if instructions.len() <= capacity {
+            (&mut mmap[..]).write_all(&instructions)?;
+            return Ok((mmap.make_exec()?, instructions.len()));
+        }
+
+        capacity = (instructions.len() + PAGE_SIZE).next_multiple_of(PAGE_SIZE);
+    }
+}
+
 pub fn codegen_basic_block(
     environment: &mut Environment,
     basic_block_id: &BasicBlockId,
+    type_info: &TypeInfo,
     dump_disassembly: bool,
 ) -> Result<Mmap> {
     let mut asm = CodeAssembler::new(64)?;
     let mut context = CodegenContext::new();
+    context.stack = type_info.stack.clone();
 
     let instructions = environment
         .get_basic_block(basic_block_id)
         .expect("invalid basic block id")
         .instructions();
 
-    let stack_allocation = environment
+    // the blocks of a function share its frame, so every one of them gets
+    // the same layout, whichever scope it belongs to
+    let frame_size = environment
         .get_basic_block(basic_block_id)
         .expect("invalid basic block id")
-        .stack_allocation();
+        .frame_size();
 
-    let shadow_space = 8 * VOLATILE_REGISTERS.len();
+    let shadow_space = context.argument_count_offset() + 8;
 
-    let stack_size = shadow_space + stack_allocation;
+    let stack_size = shadow_space + frame_size;
     let extra_stack = 8 + (stack_size.checked_next_multiple_of(16).unwrap());
 
     for instruction in instructions {
+        let comparison = context.comparison.take();
+
         match instruction {
             FlowInstruction::FunctionPrologue => {
                 // asm.int3()?;
@@ -215,15 +371,52 @@ pub fn codegen_basic_block(
                 }
 
                 for (index, register) in VOLATILE_REGISTERS.iter().enumerate() {
-                    asm.mov(rsp + (((index + 0) * 8) as i8), *register)?;
+                    asm.mov(rsp + ((index * 8) as i8), *register)?;
                 }
+
+                for (index, register) in ARGUMENT_REGISTERS.iter().enumerate() {
+                    asm.mov(
+                        rsp + context.parameter_offset(index, extra_stack),
+                        *register,
+                    )?;
+                }
+
+                asm.mov(rsp + context.context_offset(), rax)?;
+                asm.mov(
+                    rsp + context.argument_count_offset(),
+                    ARGUMENT_COUNT_REGISTER,
+                )?;
+            }
+
+            FlowInstruction::CreateEnvironmentRecord(size) => {
+                asm.mov(rcx, rsp + context.context_offset())?;
+                asm.mov(rdx, size as u64)?;
+                codegen_native_call(&mut asm, allocate_environment_record as *const () as u64)?;
+                asm.mov(rsp + context.context_offset(), rax)?;
+            }
+
+            FlowInstruction::CopyEnvironmentRecord(size) => {
+                asm.mov(rcx, rsp + context.context_offset())?;
+                asm.mov(rdx, size as u64)?;
+                codegen_native_call(&mut asm, copy_environment_record as *const () as u64)?;
+                asm.mov(rsp + context.context_offset(), rax)?;
+            }
+
+            FlowInstruction::PopEnvironmentRecord => {
+                codegen_environment_record(&mut asm, &context, rax, 1)?;
+                asm.mov(rsp + context.context_offset(), rax)?;
             }
 
             FlowInstruction::FunctionEpilogue => {
                 for (index, register) in VOLATILE_REGISTERS.iter().enumerate() {
-                    asm.mov(*register, rsp + (((index + 0) * 8) as i8))?;
+                    asm.mov(*register, rsp + ((index * 8) as i8))?;
                 }
 
+                asm.mov(
+                    ARGUMENT_COUNT_REGISTER,
+                    rsp + context.argument_count_offset(),
+                )?;
+
                 if extra_stack > 0 {
                     asm.add(rsp, extra_stack as i32)?;
                 }
@@ -234,115 +427,397 @@ pub fn codegen_basic_block(
 
             FlowInstruction::Assign => {
                 let (left_entry, _left) = context.pop();
-                let (_right_entry, right) = context.pop();
+                let (right_entry, right) = context.pop();
 
-                let (left, id) = match &left_entry {
+                let target = match &left_entry {
                     CodegenStackEntry::Id(id) => {
                         let value = environment
                             .get_basic_block(basic_block_id)
-                            .clone()
                             .expect("invalid basic block id")
-                            .lookup(&id);
-
-                        (value, Some(id))
+                            .lookup(id);
+
+                        match value {
+                            Some(Value::StackVariable { offset }) => rsp + shadow_space + offset,
+                            Some(Value::FunctionParameter(index)) => {
+                                rsp + context.parameter_offset(index, extra_stack)
+                            }
+                            Some(Value::CapturedVariable { slot: _ }) => {
+                                unimplemented!("assignment to captured variable {:?} by name", id)
+                            }
+
+                            Some(Value::Function {
+                                body: _,
+                                id,
+                                params: _,
+                            }) => unimplemented!("assignment to function {:?}", id),
+                            Some(Value::SystemFunction(_)) => {
+                                unimplemented!("assignment to system function {:?}", id)
+                            }
+                            Some(Value::Undefined) => unimplemented!("assignment to undefined"),
+
+                            None => {
+                                unimplemented!("assignment left hand side {:?} not defined", id)
+                            }
+                        }
                     }
-                    CodegenStackEntry::StackVariable(offset) => {
-                        (Some(Value::StackVariable { offset: *offset }), None)
+                    CodegenStackEntry::StackVariable(offset) => rsp + shadow_space + *offset,
+                    CodegenStackEntry::FunctionParameter(index) => {
+                        rsp + context.parameter_offset(*index, extra_stack)
+                    }
+                    CodegenStackEntry::CapturedVariable { depth, slot } => {
+                        codegen_environment_record(&mut asm, &context, rax, *depth)?;
+                        rax + environment_slot_offset(*slot)
                     }
                     other => unimplemented!("assignment to left hand side {:?}", other),
                 };
 
-                match left {
-                    Some(Value::StackVariable { offset }) => {
-                        asm.mov(rsp + shadow_space + (8 * offset), right)?;
-                    }
-                    Some(Value::FunctionParameter(_index)) => {
-                        todo!("assignment to function parameter")
-                    }
+                asm.mov(target, right)?;
 
-                    Some(Value::Function {
-                        body: _,
-                        id,
-                        params: _,
-                    }) => unimplemented!("assignment to function {:?}", id),
-                    Some(Value::SystemFunction(_)) => {
-                        unimplemented!("assignment to system function {:?}", id)
-                    }
+                // the assignment evaluates to the assigned value
+                context.push(right_entry);
+            }
 
-                    None => unimplemented!("assignment left hand side {:?} not defined", id),
-                }
+            // this doesn't return, and the stack is left as an assignment
+            // would leave it
+            FlowInstruction::AssignConstant => {
+                context.pop();
+                let (right_entry, _right) = context.pop();
+
+                codegen_native_call(&mut asm, assignment_to_constant as *const () as u64)?;
+
+                context.push(right_entry);
+            }
+            FlowInstruction::PushLiteralBoolean(literal) => {
+                let register = context.push(CodegenStackEntry::Boolean);
+                asm.mov(register, if literal { TRUE } else { FALSE })?;
+            }
+            FlowInstruction::PushLiteralString(literal) => {
+                let register = context.push(CodegenStackEntry::String);
+                asm.mov(register, intern_string(&literal))?;
             }
-            FlowInstruction::PushLiteralBoolean(_literal) => todo!(),
-            FlowInstruction::PushLiteralString(_literal) => todo!(),
             FlowInstruction::PushLiteralNumber(literal) => {
                 let register = context.push(CodegenStackEntry::Number);
-                asm.mov(register, literal as u64)?;
+                asm.mov(register, literal.to_bits())?;
+            }
+            FlowInstruction::PushLiteralNull => {
+                let register = context.push(CodegenStackEntry::Null);
+                asm.mov(register, NULL)?;
+            }
+            FlowInstruction::PushLiteralUndefined => {
+                let register = context.push(CodegenStackEntry::Undefined);
+                asm.mov(register, UNDEFINED)?;
+            }
+            FlowInstruction::PushUninitialized => {
+                let register = context.push(CodegenStackEntry::Dynamic);
+                asm.mov(register, UNINITIALIZED)?;
+            }
+            FlowInstruction::CheckInitialized(name) => {
+                let (entry, register) = context.pop();
+                context.push(entry);
+
+                let mut initialized = asm.create_label();
+                asm.mov(rax, UNINITIALIZED)?;
+                asm.cmp(register, rax)?;
+                asm.jne(initialized)?;
+
+                asm.mov(rcx, intern_string(&name))?;
+                codegen_native_call(&mut asm, binding_not_initialized as *const () as u64)?;
+
+                asm.set_label(&mut initialized)?;
+                asm.nop()?;
             }
-            FlowInstruction::PushLiteralNull => todo!(),
             FlowInstruction::PushFunctionParameter(index) => {
                 // TODO what type?!?
-                let register = context.push(CodegenStackEntry::Number);
-                asm.mov(register, context.argument_register(index))?;
+                let register = context.push(CodegenStackEntry::FunctionParameter(index));
+                asm.mov(register, rsp + context.parameter_offset(index, extra_stack))?;
+
+                // a parameter without an argument is undefined
+                asm.mov(rax, UNDEFINED)?;
+                asm.cmp(
+                    qword_ptr(rsp + context.argument_count_offset()),
+                    index as i32,
+                )?;
+                asm.cmovbe(register, rax)?;
             }
             FlowInstruction::PushStackVariable(offset) => {
                 let register = context.push(CodegenStackEntry::StackVariable(offset));
-                asm.mov(register, rsp + shadow_space + (8 * offset))?;
+                asm.mov(register, rsp + shadow_space + offset)?;
+            }
+            FlowInstruction::PushCapturedVariable { depth, slot } => {
+                let register = context.push(CodegenStackEntry::CapturedVariable { depth, slot });
+                codegen_environment_record(&mut asm, &context, register, depth)?;
+                asm.mov(
+                    register,
+                    qword_ptr(register + environment_slot_offset(slot)),
+                )?;
+            }
+            FlowInstruction::CreateObject(names) => {
+                let shape = names
+                    .iter()
+                    .fold(ShapeId::EMPTY, |shape, name| shape.with_property(name));
+
+                asm.mov(rcx, shape.0 as u64)?;
+                codegen_native_call(&mut asm, allocate_object as *const () as u64)?;
+
+                let register = context.push(CodegenStackEntry::Object(shape));
+                asm.mov(register, rax)?;
+            }
+
+            FlowInstruction::GetProperty(name) => {
+                let (entry, object) = context.pop();
+
+                match (&entry, name.as_str()) {
+                    // objects don't have prototypes yet, so an object without
+                    // the property has it undefined
+                    (CodegenStackEntry::Object(shape), name) => match shape.slot(name) {
+                        Some(slot) => {
+                            codegen_object_slots(&mut asm, object, object)?;
+                            let register = context.push(CodegenStackEntry::Dynamic);
+                            asm.mov(register, qword_ptr(object + 8 * slot))?;
+                        }
+                        None => {
+                            let register = context.push(CodegenStackEntry::Undefined);
+                            asm.mov(register, UNDEFINED)?;
+                        }
+                    },
+
+                    (entry, name) if entry.is_dynamic() => {
+                        let cache = environment.create_inline_cache(
+                            *basic_block_id,
+                            PropertyAccess::Load,
+                            name.to_string(),
+                        );
+
+                        let stub = environment.inline_cache_stub(PropertyAccess::Load);
+
+                        let register = context.push(CodegenStackEntry::Dynamic);
+                        codegen_cached_load(&mut asm, stub, cache, object, register)?;
+                    }
+
+                    (entry, name) => {
+                        asm.mov(rcx, object)?;
+                        asm.mov(rdx, intern_string(name))?;
+                        codegen_native_call(&mut asm, value_get_property as *const () as u64)?;
+
+                        let register = if *entry == CodegenStackEntry::String && name == "length" {
+                            context.push(CodegenStackEntry::Number)
+                        } else {
+                            context.push(CodegenStackEntry::Dynamic)
+                        };
+                        asm.mov(register, rax)?;
+                    }
+                }
+            }
+
+            FlowInstruction::SetProperty(name) => {
+                let (value_entry, value) = context.pop();
+                let (object_entry, object) = context.pop();
+
+                match object_entry {
+                    CodegenStackEntry::Object(shape) if shape.slot(&name).is_some() => {
+                        let slot = shape.slot(&name).unwrap();
+                        codegen_object_slots(&mut asm, object, object)?;
+                        asm.mov(qword_ptr(object + 8 * slot), value)?;
+                    }
+                    entry if entry.is_dynamic() => {
+                        let cache = environment.create_inline_cache(
+                            *basic_block_id,
+                            PropertyAccess::Store,
+                            name.clone(),
+                        );
+
+                        let stub = environment.inline_cache_stub(PropertyAccess::Store);
+
+                        codegen_cached_store(&mut asm, stub, cache, object, value)?;
+                    }
+                    _ => {
+                        asm.mov(rcx, object)?;
+                        asm.mov(rdx, intern_string(&name))?;
+                        asm.mov(r8, value)?;
+                        codegen_native_call(&mut asm, value_set_property as *const () as u64)?;
+                    }
+                }
+
+                // the assignment evaluates to the assigned value
+                let register = context.push(value_entry);
+                asm.mov(register, value)?;
             }
+
+            // the object was created with the shape of the literal, so the
+            // slot is known
+            FlowInstruction::InitProperty(name) => {
+                let (_value_entry, value) = context.pop();
+                let (object_entry, object) = context.pop();
+
+                let slot = match &object_entry {
+                    CodegenStackEntry::Object(shape) => shape.slot(&name),
+                    _ => None,
+                }
+                .expect("object literal property");
+
+                codegen_object_slots(&mut asm, rax, object)?;
+                asm.mov(qword_ptr(rax + 8 * slot), value)?;
+
+                let register = context.push(object_entry);
+                asm.mov(register, object)?;
+            }
+
+            FlowInstruction::GetComputedProperty => {
+                let (_key_entry, key) = context.pop();
+                let (_object_entry, object) = context.pop();
+
+                asm.mov(rcx, object)?;
+                asm.mov(rdx, key)?;
+                codegen_native_call(&mut asm, value_get_computed_property as *const () as u64)?;
+
+                let register = context.push(CodegenStackEntry::Dynamic);
+                asm.mov(register, rax)?;
+            }
+
+            FlowInstruction::SetComputedProperty => {
+                let (value_entry, value) = context.pop();
+                let (_key_entry, key) = context.pop();
+                let (_object_entry, object) = context.pop();
+
+                asm.mov(rcx, object)?;
+                asm.mov(rdx, key)?;
+                asm.mov(r8, value)?;
+                codegen_native_call(&mut asm, value_set_computed_property as *const () as u64)?;
+
+                let register = context.push(value_entry);
+                asm.mov(register, value)?;
+            }
+
             FlowInstruction::ApplyBinaryOperator(operator) => {
                 let (right_entry, right) = context.pop();
                 let (left_entry, left) = context.pop();
 
-                if left_entry != CodegenStackEntry::Number {
-                    todo!("binary operator on left type {:?}", left_entry);
+                if Condition::for_integers(operator).is_some() {
+                    codegen_comparison(
+                        &mut asm,
+                        &mut context,
+                        operator,
+                        (left_entry, left),
+                        (right_entry, right),
+                    )?;
+                } else {
+                    codegen_arithmetic(
+                        &mut asm,
+                        &mut context,
+                        operator,
+                        (left_entry, left),
+                        (right_entry, right),
+                    )?;
                 }
+            }
 
-                if right_entry != CodegenStackEntry::Number {
-                    todo!("binary operator on right type {:?}", right_entry);
-                }
+            FlowInstruction::ApplyUnaryOperator(operator) => {
+                let (entry, register) = context.pop();
+                codegen_unary(&mut asm, &mut context, operator, entry, register)?;
+            }
 
-                let _destination = context.push(CodegenStackEntry::Number);
+            FlowInstruction::StoreArgument(offset) => {
+                let (_entry, argument) = context.pop();
+                asm.mov(rsp + shadow_space + offset, argument)?;
+            }
 
-                match operator {
-                    BinaryOperator::Plus => asm.add(left, right)?,
-                    op => todo!("codegen for binary operator {:?}", op),
-                };
+            FlowInstruction::CreateArgumentsObject => {
+                codegen_arguments_object(&mut asm, &mut context, extra_stack, 0)?;
+            }
+            FlowInstruction::CreateRestParameter(start) => {
+                codegen_arguments_object(&mut asm, &mut context, extra_stack, start)?;
             }
 
             FlowInstruction::CallFunction {
                 basic_block_id,
-                argument_count,
+                arguments,
+                context_depth,
             } => {
-                for argument_index in 0..argument_count {
-                    let (stack_entry, stack_register) = context.pop();
+                let block_fn = environment.basic_block_fn(basic_block_id, TypeInfo::default());
 
-                    if stack_entry != CodegenStackEntry::Number {
-                        todo!("non-number argument");
-                    }
+                // the callee sees the environment record where it was declared
+                codegen_environment_record(&mut asm, &context, rax, context_depth)?;
 
-                    let argument_register = context.argument_register(argument_index);
-                    asm.mov(argument_register, stack_register)?;
+                let stack_arguments_size =
+                    codegen_call_arguments(&mut asm, arguments, shadow_space)?;
+
+                asm.call(block_fn as usize as u64)?;
+
+                if stack_arguments_size > 0 {
+                    asm.add(rsp, stack_arguments_size as i32)?;
+                }
+
+                let return_value = context.push(CodegenStackEntry::Dynamic);
+                asm.mov(return_value, rax)?;
+            }
+
+            FlowInstruction::PushFunction {
+                basic_block_id,
+                context_depth,
+            } => {
+                let block_fn = environment.basic_block_fn(basic_block_id, TypeInfo::default());
+
+                asm.mov(rcx, block_fn as usize as u64)?;
+                codegen_environment_record(&mut asm, &context, rdx, context_depth)?;
+                codegen_native_call(&mut asm, allocate_function_object as *const () as u64)?;
+
+                let register = context.push(CodegenStackEntry::Function);
+                asm.mov(register, rax)?;
+            }
+
+            FlowInstruction::CallValue { arguments } => {
+                let stack_arguments_size =
+                    codegen_call_arguments(&mut asm, arguments, shadow_space)?;
+
+                let (callee_entry, callee) = context.pop();
+                match callee_entry {
+                    CodegenStackEntry::Function => {}
+                    entry if entry.is_dynamic() => {
+                        codegen_function_check(&mut asm, callee)?;
+                    }
+                    // any other value is known not to be a function, and
+                    // this doesn't return
+                    _ => {
+                        asm.mov(rcx, callee)?;
+                        codegen_native_call(&mut asm, value_not_a_function as *const () as u64)?;
+                    }
                 }
 
-                let type_info = TypeInfo;
-                let block_fn = environment.basic_block_fn(basic_block_id, type_info);
+                asm.mov(rax, PAYLOAD_MASK)?;
+                asm.and(callee, rax)?;
+                asm.mov(rax, qword_ptr(callee + FUNCTION_CONTEXT_OFFSET))?;
+                asm.call(qword_ptr(callee + FUNCTION_CODE_OFFSET))?;
 
-                // asm.sub(rsp, 0x28)?;
-                asm.call(block_fn as u64)?;
-                // asm.add(rsp, 0x28)?;
+                if stack_arguments_size > 0 {
+                    asm.add(rsp, stack_arguments_size as i32)?;
+                }
 
-                let return_value = context.push(CodegenStackEntry::Number);
+                let return_value = context.push(CodegenStackEntry::Dynamic);
                 asm.mov(return_value, rax)?;
             }
 
-            FlowInstruction::CallSystemFunction(function) => {
-                let mut argument_entries = Vec::new();
+            FlowInstruction::CallSystemFunction {
+                function,
+                argument_count,
+                stack_offset,
+            } => {
+                // the types of the arguments stored in the frame aren't known
+                let register_argument_count = argument_count.min(REGISTER_ARGUMENT_COUNT);
+                let stack_argument_count = argument_count - register_argument_count;
+                let mut argument_entries = vec![CodegenStackEntry::Dynamic; stack_argument_count];
 
-                for index in 0..function.arity() {
+                for index in (0..register_argument_count).rev() {
                     let (entry, argument) = context.pop();
                     argument_entries.push(entry);
 
                     asm.mov(context.argument_register(index), argument)?;
                 }
+                argument_entries.reverse();
+
+                // a handler with parameters past the arguments sees undefined
+                for index in argument_count..ARGUMENT_REGISTERS.len() {
+                    asm.mov(context.argument_register(index), UNDEFINED)?;
+                }
 
                 let callee = function.handler_fn(&argument_entries);
                 let callee = callee.unwrap_or_else(|| {
@@ -353,9 +828,14 @@ pub fn codegen_basic_block(
                     )
                 });
 
-                asm.call(callee as *const u8 as u64)?;
+                codegen_native_call_with_stack_arguments(
+                    &mut asm,
+                    callee as *const u8 as u64,
+                    stack_argument_count,
+                    shadow_space + stack_offset,
+                )?;
 
-                let return_value = context.push(CodegenStackEntry::Number);
+                let return_value = context.push(CodegenStackEntry::Dynamic);
                 asm.mov(return_value, rax)?;
             }
 
@@ -367,8 +847,7 @@ pub fn codegen_basic_block(
             }
 
             FlowInstruction::Return => {
-                asm.mov(rax, 0u64)?;
-                // asm.ret()?;
+                asm.mov(rax, UNDEFINED)?;
             }
 
             FlowInstruction::GoToBlock(_basic_block_id) => todo!("GoToBlock instruction"),
@@ -376,6 +855,51 @@ pub fn codegen_basic_block(
             FlowInstruction::DiscardValue => {
                 context.pop();
             }
+
+            // copying the value leaves the flags alone
+            FlowInstruction::Duplicate => {
+                context.comparison = comparison;
+
+                let (entry, source) = context.pop();
+                context.push(entry.clone());
+
+                let destination = context.push(entry);
+                asm.mov(destination, source)?;
+            }
+
+            FlowInstruction::Duplicate2 => {
+                let (key_entry, key) = context.pop();
+                let (object_entry, object) = context.pop();
+                context.push(object_entry.clone());
+                context.push(key_entry.clone());
+
+                let object_copy = context.push(object_entry);
+                asm.mov(object_copy, object)?;
+                let key_copy = context.push(key_entry);
+                asm.mov(key_copy, key)?;
+            }
+
+            FlowInstruction::DuplicateUnder(count) => {
+                let (entry, source) = context.pop();
+                let under: Vec<_> = (0..count).map(|_| context.pop()).collect();
+
+                let bottom = context.push(entry.clone());
+                let shifted: Vec<_> = under
+                    .iter()
+                    .rev()
+                    .map(|(entry, _)| context.push(entry.clone()))
+                    .collect();
+                let top = context.push(entry);
+
+                // each value moves up a register, so they're moved from the
+                // top down, and the copy on top is where the bottom one
+                // comes from
+                asm.mov(top, source)?;
+                for ((_entry, register), destination) in under.iter().zip(shifted.iter().rev()) {
+                    asm.mov(*destination, *register)?;
+                }
+                asm.mov(bottom, top)?;
+            }
         }
     }
 
@@ -388,37 +912,1018 @@ pub fn codegen_basic_block(
     for instruction in tail_instructions {
         match instruction {
             TailInstruction::Jump(target_block_id) => {
-                let target = environment.basic_block_fn(target_block_id, TypeInfo) as u64;
+                let type_info = TypeInfo {
+                    stack: context.stack.clone(),
+                };
+                let target = environment.basic_block_fn(target_block_id, type_info) as usize as u64;
                 asm.jmp(target)?;
             }
-            TailInstruction::ConditionalJump(_target_block_id) => todo!("conditional jump tail"),
+            TailInstruction::ConditionalJump {
+                then_block,
+                else_block,
+            } => {
+                let comparison = context.comparison.take();
+                let (condition_entry, condition) = context.pop();
+
+                let type_info = TypeInfo {
+                    stack: context.stack.clone(),
+                };
+                let then_target =
+                    environment.basic_block_fn(then_block, type_info.clone()) as usize as u64;
+                let else_target = environment.basic_block_fn(else_block, type_info) as usize as u64;
+
+                match condition_entry {
+                    _ if comparison.is_some() => {
+                        comparison
+                            .unwrap()
+                            .branch(&mut asm, then_target, else_target)?;
+                    }
+
+                    CodegenStackEntry::Null | CodegenStackEntry::Undefined => {
+                        asm.jmp(else_target)?;
+                    }
+
+                    CodegenStackEntry::Function | CodegenStackEntry::Object(_) => {
+                        asm.jmp(then_target)?;
+                    }
+
+                    entry => {
+                        codegen_truthiness(&mut asm, &context, &entry, condition)?;
+                        Condition::NotEqual.branch(&mut asm, then_target, else_target)?;
+                    }
+                }
+            }
         }
     }
 
-    let mut mmap = memmap::MmapMut::map_anon(4096)?;
-    let rip = mmap.as_ptr() as u64;
-    let instructions = asm.assemble(rip)?;
-    (&mut mmap[..]).write_all(&instructions)?;
-    let mmap = mmap.make_exec()?;
+    let (mmap, size) = assemble_into_mapping(&mut asm)?;
 
     if dump_disassembly {
         println!("Code for block {:?}:", basic_block_id);
-        print_disassembled_code(&mmap[0..instructions.len()], rip);
+        print_disassembled_code(&mmap[0..size], mmap.as_ptr() as u64);
+    }
+
+    Ok(mmap)
+}
+
+/// Compares two values for one of the relational or equality operators, and
+/// pushes the boolean result.
+fn codegen_comparison(
+    asm: &mut CodeAssembler,
+    context: &mut CodegenContext,
+    operator: BinaryOperator,
+    (left_entry, left): (CodegenStackEntry, AsmRegister64),
+    (right_entry, right): (CodegenStackEntry, AsmRegister64),
+) -> Result<()> {
+    let strict = matches!(
+        operator,
+        BinaryOperator::TripleEquals | BinaryOperator::TripleNotEquals
+    );
+    let equality = strict
+        || matches!(
+            operator,
+            BinaryOperator::EqualsEquals | BinaryOperator::NotEquals
+        );
+    let negated = matches!(
+        operator,
+        BinaryOperator::NotEquals | BinaryOperator::TripleNotEquals
+    );
+
+    // booleans compare as the numbers 0 and 1, except under strict equality
+    let numeric =
+        |entry: &CodegenStackEntry| entry.is_number() || *entry == CodegenStackEntry::Boolean;
+    let nullish = |entry: &CodegenStackEntry| {
+        matches!(
+            entry,
+            CodegenStackEntry::Null | CodegenStackEntry::Undefined
+        )
+    };
+    let may_be_string =
+        |entry: &CodegenStackEntry| entry.is_dynamic() || *entry == CodegenStackEntry::String;
+    let dynamic = left_entry.is_dynamic() || right_entry.is_dynamic();
+
+    let condition = if equality && !dynamic && (nullish(&left_entry) || nullish(&right_entry)) {
+        // null and undefined only equal themselves, and each other unless
+        // the comparison is strict
+        let equal = if strict {
+            left_entry == right_entry
+        } else {
+            nullish(&left_entry) && nullish(&right_entry)
+        };
+
+        let destination = context.push(CodegenStackEntry::Boolean);
+        asm.mov(destination, if equal != negated { TRUE } else { FALSE })?;
+        return Ok(());
+    } else if left_entry == CodegenStackEntry::Boolean && right_entry == CodegenStackEntry::Boolean
+    {
+        asm.cmp(left, right)?;
+        Condition::for_integers(operator).expect("comparison operator")
+    } else if (numeric(&left_entry)
+        && numeric(&right_entry)
+        && (!strict || left_entry == right_entry))
+        || (!equality && !may_be_string(&left_entry) && !may_be_string(&right_entry))
+    {
+        let (condition, swap) = Condition::for_numbers(operator).expect("comparison operator");
+        codegen_to_number(asm, context, &left_entry, left)?;
+        codegen_to_number(asm, context, &right_entry, right)?;
+        asm.movq(xmm0, left)?;
+        asm.movq(xmm1, right)?;
+
+        if swap {
+            asm.ucomisd(xmm1, xmm0)?;
+        } else {
+            asm.ucomisd(xmm0, xmm1)?;
+        }
+
+        condition
+    } else if equality {
+        let helper = if strict {
+            value_strict_equals as *const () as u64
+        } else {
+            value_loose_equals as *const () as u64
+        };
+
+        asm.mov(rcx, left)?;
+        asm.mov(rdx, right)?;
+        codegen_native_call(asm, helper)?;
+        asm.test(eax, eax)?;
+
+        if negated {
+            Condition::Equal
+        } else {
+            Condition::NotEqual
+        }
+    } else {
+        // the operands may be strings, which compare by their characters,
+        // so the helper orders them and the result is compared with 0
+        let (condition, swap) = Condition::for_numbers(operator).expect("comparison operator");
+        asm.mov(rcx, left)?;
+        asm.mov(rdx, right)?;
+        codegen_native_call(asm, value_compare as *const () as u64)?;
+        asm.movq(xmm0, rax)?;
+        asm.xorpd(xmm1, xmm1)?;
+
+        if swap {
+            asm.ucomisd(xmm1, xmm0)?;
+        } else {
+            asm.ucomisd(xmm0, xmm1)?;
+        }
+
+        condition
+    };
+
+    // setting the low byte of `false` turns it into `true`
+    let destination = context.push(CodegenStackEntry::Boolean);
+    asm.mov(destination, FALSE)?;
+    let condition = condition.set(asm, context.byte_register(destination))?;
+    context.comparison = Some(condition);
+
+    Ok(())
+}
+
+/// Applies one of the arithmetic operators to two values, which are
+/// converted to numbers, and pushes the result.
+fn codegen_arithmetic(
+    asm: &mut CodeAssembler,
+    context: &mut CodegenContext,
+    operator: BinaryOperator,
+    (left_entry, left): (CodegenStackEntry, AsmRegister64),
+    (right_entry, right): (CodegenStackEntry, AsmRegister64),
+) -> Result<()> {
+    // functions and objects convert to strings too, so adding one
+    // concatenates
+    let may_be_string = |entry: &CodegenStackEntry| {
+        entry.is_dynamic()
+            || matches!(
+                entry,
+                CodegenStackEntry::String
+                    | CodegenStackEntry::Function
+                    | CodegenStackEntry::Object(_)
+                    | CodegenStackEntry::Id(_)
+            )
+    };
+
+    if operator == BinaryOperator::Plus
+        && (may_be_string(&left_entry) || may_be_string(&right_entry))
+    {
+        return codegen_concatenation(asm, context, (left_entry, left), (right_entry, right));
+    }
+
+    codegen_to_number(asm, context, &left_entry, left)?;
+    codegen_to_number(asm, context, &right_entry, right)?;
+
+    asm.movq(xmm0, left)?;
+    asm.movq(xmm1, right)?;
+
+    let destination = context.push(CodegenStackEntry::Number);
+
+    match operator {
+        BinaryOperator::Plus => asm.addsd(xmm0, xmm1)?,
+        BinaryOperator::Minus => asm.subsd(xmm0, xmm1)?,
+        BinaryOperator::Asterisk => asm.mulsd(xmm0, xmm1)?,
+        BinaryOperator::Slash => asm.divsd(xmm0, xmm1)?,
+        BinaryOperator::Percent => {
+            codegen_native_call(asm, number_remainder as *const () as u64)?;
+        }
+        BinaryOperator::Exponentiation => {
+            codegen_native_call(asm, number_power as *const () as u64)?;
+        }
+        BinaryOperator::BitwiseOr
+        | BinaryOperator::BitwiseXor
+        | BinaryOperator::BitwiseAnd
+        | BinaryOperator::ZeroFillLeftShift
+        | BinaryOperator::SignedRightShift
+        | BinaryOperator::ZeroFillRightShift => {
+            codegen_integer_operator(asm, context, operator, destination, right)?;
+        }
+        op => todo!("codegen for binary operator {:?}", op),
+    };
+
+    asm.movq(destination, xmm0)?;
+    Ok(())
+}
+/// The `+` operator on operands that may be strings, which concatenates them
+/// if either is one once converted to a primitive. Operands that may both be
+/// numbers are checked for that first, and added inline if they are.
+fn codegen_concatenation(
+    asm: &mut CodeAssembler,
+    context: &mut CodegenContext,
+    (left_entry, left): (CodegenStackEntry, AsmRegister64),
+    (right_entry, right): (CodegenStackEntry, AsmRegister64),
+) -> Result<()> {
+    let result_entry =
+        if left_entry == CodegenStackEntry::String || right_entry == CodegenStackEntry::String {
+            CodegenStackEntry::String
+        } else {
+            CodegenStackEntry::Dynamic
+        };
+    let destination = context.push(result_entry);
+
+    let may_be_number = |entry: &CodegenStackEntry| entry.is_number() || entry.is_dynamic();
+    let mut done = None;
+
+    if may_be_number(&left_entry) && may_be_number(&right_entry) {
+        let mut not_numbers = asm.create_label();
+        for (entry, register) in [(&left_entry, left), (&right_entry, right)] {
+            if entry.is_dynamic() {
+                asm.mov(rax, FIRST_TAG)?;
+                asm.cmp(register, rax)?;
+                asm.jae(not_numbers)?;
+            }
+        }
+
+        asm.movq(xmm0, left)?;
+        asm.movq(xmm1, right)?;
+        asm.addsd(xmm0, xmm1)?;
+        asm.movq(destination, xmm0)?;
+
+        let label = asm.create_label();
+        asm.jmp(label)?;
+        done = Some(label);
+
+        asm.set_label(&mut not_numbers)?;
+    }
+
+    asm.mov(rcx, left)?;
+    asm.mov(rdx, right)?;
+    codegen_native_call(asm, value_add as *const () as u64)?;
+    asm.mov(destination, rax)?;
+
+    if let Some(mut done) = done {
+        asm.set_label(&mut done)?;
+        asm.nop()?;
+    }
+
+    Ok(())
+}
+
+/// Applies a bitwise or shift operator to the numbers in `xmm0` and `xmm1`,
+/// which are converted to 32-bit integers first. `left` and `right` are
+/// used as scratch registers, and the result is left in `xmm0`.
+fn codegen_integer_operator(
+    asm: &mut CodeAssembler,
+    context: &CodegenContext,
+    operator: BinaryOperator,
+    left: AsmRegister64,
+    right: AsmRegister64,
+) -> Result<()> {
+    // keeping the low 32 bits of the truncated value wraps around like
+    // ToInt32 does, and NaN and the infinities truncate to a value whose
+    // low 32 bits are 0
+    asm.cvttsd2si(left, xmm0)?;
+    asm.cvttsd2si(right, xmm1)?;
+
+    let left_dword = context.dword_register(left);
+    let right_dword = context.dword_register(right);
+
+    match operator {
+        BinaryOperator::BitwiseOr => asm.or(left_dword, right_dword)?,
+        BinaryOperator::BitwiseXor => asm.xor(left_dword, right_dword)?,
+        BinaryOperator::BitwiseAnd => asm.and(left_dword, right_dword)?,
+
+        // the processor takes the shift count modulo 32, as JS does
+        shift => {
+            asm.mov(ecx, right_dword)?;
+
+            match shift {
+                BinaryOperator::ZeroFillLeftShift => asm.shl(left_dword, cl)?,
+                BinaryOperator::SignedRightShift => asm.sar(left_dword, cl)?,
+                BinaryOperator::ZeroFillRightShift => asm.shr(left_dword, cl)?,
+                other => unreachable!("not an integer operator: {:?}", other),
+            }
+        }
+    }
+
+    // the result is signed, except for `>>>`, whose result the 32-bit
+    // operation has already zero-extended
+    if operator != BinaryOperator::ZeroFillRightShift {
+        asm.movsxd(left, left_dword)?;
+    }
+
+    asm.cvtsi2sd(xmm0, left)?;
+    Ok(())
+}
+
+/// Applies a unary operator to a value, and pushes the result.
+fn codegen_unary(
+    asm: &mut CodeAssembler,
+    context: &mut CodegenContext,
+    operator: UnaryOperator,
+    entry: CodegenStackEntry,
+    value: AsmRegister64,
+) -> Result<()> {
+    match operator {
+        UnaryOperator::Plus => {
+            codegen_to_number(asm, context, &entry, value)?;
+            context.push(CodegenStackEntry::Number);
+        }
+
+        UnaryOperator::Minus => {
+            codegen_to_number(asm, context, &entry, value)?;
+            asm.btc(value, 63)?;
+            context.push(CodegenStackEntry::Number);
+        }
+
+        // converts to a 32-bit integer, which wraps around, and back
+        UnaryOperator::BitwiseNot => {
+            codegen_to_number(asm, context, &entry, value)?;
+            asm.movq(xmm0, value)?;
+            asm.cvttsd2si(value, xmm0)?;
+            asm.not(value)?;
+            asm.movsxd(value, context.dword_register(value))?;
+            asm.cvtsi2sd(xmm0, value)?;
+            asm.movq(value, xmm0)?;
+            context.push(CodegenStackEntry::Number);
+        }
+
+        UnaryOperator::LogicalNot => match entry {
+            CodegenStackEntry::Null | CodegenStackEntry::Undefined => {
+                let destination = context.push(CodegenStackEntry::Boolean);
+                asm.mov(destination, TRUE)?;
+            }
+
+            CodegenStackEntry::Id(_)
+            | CodegenStackEntry::Function
+            | CodegenStackEntry::Object(_) => {
+                let destination = context.push(CodegenStackEntry::Boolean);
+                asm.mov(destination, FALSE)?;
+            }
+
+            entry => {
+                codegen_truthiness(asm, context, &entry, value)?;
+
+                let destination = context.push(CodegenStackEntry::Boolean);
+                asm.mov(destination, FALSE)?;
+                asm.sete(context.byte_register(destination))?;
+                context.comparison = Some(Condition::Equal);
+            }
+        },
+
+        UnaryOperator::Typeof => {
+            let type_name = match entry {
+                CodegenStackEntry::Boolean => Some("boolean"),
+                CodegenStackEntry::String => Some("string"),
+                CodegenStackEntry::Number => Some("number"),
+                CodegenStackEntry::Null | CodegenStackEntry::Object(_) => Some("object"),
+                CodegenStackEntry::Undefined => Some("undefined"),
+                CodegenStackEntry::Id(_) | CodegenStackEntry::Function => Some("function"),
+                _ => None,
+            };
+
+            match type_name {
+                Some(type_name) => {
+                    let destination = context.push(CodegenStackEntry::String);
+                    asm.mov(destination, intern_string(type_name))?;
+                }
+                None => {
+                    asm.mov(rcx, value)?;
+                    codegen_native_call(asm, value_type_of as *const () as u64)?;
+
+                    let destination = context.push(CodegenStackEntry::String);
+                    asm.mov(destination, rax)?;
+                }
+            }
+        }
+
+        operator => unimplemented!("codegen for unary operator {:?}", operator),
+    }
+
+    Ok(())
+}
+
+/// Converts a value to a number in place.
+fn codegen_to_number(
+    asm: &mut CodeAssembler,
+    context: &CodegenContext,
+    entry: &CodegenStackEntry,
+    value: AsmRegister64,
+) -> Result<()> {
+    match entry {
+        CodegenStackEntry::Number => {}
+
+        // the low 32 bits of a boolean are 0 or 1
+        CodegenStackEntry::Boolean => {
+            asm.cvtsi2sd(xmm0, context.dword_register(value))?;
+            asm.movq(value, xmm0)?;
+        }
+
+        CodegenStackEntry::Null => asm.mov(value, 0f64.to_bits())?,
+        CodegenStackEntry::Undefined
+        | CodegenStackEntry::Function
+        | CodegenStackEntry::Object(_)
+        | CodegenStackEntry::Id(_) => asm.mov(value, f64::NAN.to_bits())?,
+
+        CodegenStackEntry::String => {
+            asm.mov(rcx, value)?;
+            codegen_native_call(asm, value_to_number as *const () as u64)?;
+            asm.mov(value, rax)?;
+        }
+
+        // numbers are stored as they are, so only tagged values need
+        // converting
+        _ => {
+            let mut converted = asm.create_label();
+            asm.mov(rax, FIRST_TAG)?;
+            asm.cmp(value, rax)?;
+            asm.jb(converted)?;
+
+            asm.mov(rcx, value)?;
+            codegen_native_call(asm, value_to_number as *const () as u64)?;
+            asm.mov(value, rax)?;
+
+            asm.set_label(&mut converted)?;
+            asm.nop()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets the flags so that `Condition::NotEqual` holds if a value is truthy.
+fn codegen_truthiness(
+    asm: &mut CodeAssembler,
+    context: &CodegenContext,
+    entry: &CodegenStackEntry,
+    value: AsmRegister64,
+) -> Result<()> {
+    match entry {
+        CodegenStackEntry::Boolean => {
+            let byte_register = context.byte_register(value);
+            asm.test(byte_register, byte_register)?;
+        }
+
+        // 0, -0 and NaN are falsy, and NaN compares as unordered, which
+        // sets the zero flag too
+        CodegenStackEntry::Number => {
+            asm.movq(xmm0, value)?;
+            asm.xorpd(xmm1, xmm1)?;
+            asm.ucomisd(xmm0, xmm1)?;
+        }
+
+        _ => {
+            asm.mov(rcx, value)?;
+            codegen_native_call(asm, value_is_truthy as *const () as u64)?;
+            asm.test(eax, eax)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a property of the value in `object` into `destination` through an
+/// inline cache, which holds the property's slot for the shapes it has
+/// seen. The lookup is done by the environment's shared stub for loads, so
+/// each access site only passes it the cache and the object.
+fn codegen_cached_load(
+    asm: &mut CodeAssembler,
+    stub: u64,
+    cache: *mut InlineCache,
+    object: AsmRegister64,
+    destination: AsmRegister64,
+) -> Result<()> {
+    asm.mov(rcx, cache as u64)?;
+    asm.mov(rdx, object)?;
+    asm.call(stub)?;
+    asm.mov(destination, rax)?;
+
+    Ok(())
+}
+
+/// Writes `value` to a property of the value in `object` through an inline
+/// cache, like `codegen_cached_load`.
+fn codegen_cached_store(
+    asm: &mut CodeAssembler,
+    stub: u64,
+    cache: *mut InlineCache,
+    object: AsmRegister64,
+    value: AsmRegister64,
+) -> Result<()> {
+    asm.mov(rcx, cache as u64)?;
+    asm.mov(rdx, object)?;
+    asm.mov(r8, value)?;
+    asm.call(stub)?;
+
+    Ok(())
+}
+
+/// Generates the code shared by the property accesses of one kind that go
+/// through inline caches. It's called with the cache in `rcx`, the object in
+/// `rdx` and, for stores, the value in `r8`, and returns the value read in
+/// `rax`. It looks the object's shape up in the cache and counts a hit and
+/// accesses the slot directly if it's there. Anything else, including a
+/// value that isn't an object, goes through the cache's miss handler. Adding
+/// a property changes the object's shape, so that always misses.
+///
+/// Like a native call, it preserves the stack registers, but not the
+/// argument registers or the flags.
+pub fn codegen_inline_cache_stub(access: PropertyAccess, dump_disassembly: bool) -> Result<Mmap> {
+    let mut asm = CodeAssembler::new(64)?;
+    let mut miss = asm.create_label();
+
+    asm.mov(rax, rdx)?;
+    asm.shr(rax, 48)?;
+    asm.cmp(eax, (TAG_OBJECT >> 48) as i32)?;
+    asm.jne(miss)?;
+
+    asm.mov(r9, rdx)?;
+    asm.shl(r9, 16)?;
+    asm.shr(r9, 16)?;
+    asm.mov(rax, qword_ptr(r9 + OBJECT_SHAPE_OFFSET))?;
+
+    // the miss handler fills the entries in, so the code sees shapes that
+    // weren't cached when it was compiled
+    let mut entry_labels = Vec::new();
+    for index in 0..INLINE_CACHE_ENTRIES {
+        let label = asm.create_label();
+        asm.cmp(rax, qword_ptr(rcx + INLINE_CACHE_ENTRY_SIZE * index))?;
+        asm.je(label)?;
+        entry_labels.push(label);
+    }
+    asm.jmp(miss)?;
+
+    let mut hit = asm.create_label();
+    for (index, mut label) in entry_labels.into_iter().enumerate() {
+        asm.set_label(&mut label)?;
+        asm.mov(
+            rax,
+            qword_ptr(rcx + (INLINE_CACHE_ENTRY_SIZE * index + INLINE_CACHE_SLOT_OFFSET)),
+        )?;
+        asm.jmp(hit)?;
+    }
+
+    asm.set_label(&mut hit)?;
+    asm.inc(qword_ptr(rcx + INLINE_CACHE_HITS_OFFSET))?;
+    asm.mov(r9, qword_ptr(r9 + OBJECT_SLOTS_OFFSET))?;
+    match access {
+        PropertyAccess::Load => asm.mov(rax, qword_ptr(r9 + rax * 8))?,
+        PropertyAccess::Store => asm.mov(qword_ptr(r9 + rax * 8), r8)?,
+    }
+    asm.ret()?;
+
+    // the call to the stub left the stack 8 bytes off alignment, which the
+    // two saved registers keep and the shadow space makes up for
+    let miss_handler = match access {
+        PropertyAccess::Load => inline_cache_load_miss as *const () as u64,
+        PropertyAccess::Store => inline_cache_store_miss as *const () as u64,
+    };
+    asm.set_label(&mut miss)?;
+    asm.push(r10)?;
+    asm.push(r11)?;
+    asm.sub(rsp, 40)?;
+    asm.call(miss_handler)?;
+    asm.add(rsp, 40)?;
+    asm.pop(r11)?;
+    asm.pop(r10)?;
+    asm.ret()?;
+
+    let (mmap, size) = assemble_into_mapping(&mut asm)?;
+
+    if dump_disassembly {
+        println!("Inline cache stub for {:?}:", access);
+        print_disassembled_code(&mmap[0..size], mmap.as_ptr() as u64);
     }
 
     Ok(mmap)
 }
 
+/// Loads into `register` the pointer to the slots of the object in `object`.
+fn codegen_object_slots(
+    asm: &mut CodeAssembler,
+    register: AsmRegister64,
+    object: AsmRegister64,
+) -> Result<()> {
+    // shifting the tag out leaves `object` as it is, if it's `register`
+    asm.mov(register, object)?;
+    asm.shl(register, 16)?;
+    asm.shr(register, 16)?;
+    asm.mov(register, qword_ptr(register + OBJECT_SLOTS_OFFSET))?;
+
+    Ok(())
+}
+
+/// Checks that a value of unknown type is a function before it's called.
+fn codegen_function_check(asm: &mut CodeAssembler, callee: AsmRegister64) -> Result<()> {
+    let mut is_function = asm.create_label();
+    asm.mov(rax, callee)?;
+    asm.shr(rax, 48)?;
+    asm.cmp(eax, (TAG_FUNCTION >> 48) as i32)?;
+    asm.je(is_function)?;
+
+    // this doesn't return, so it can overwrite the first argument
+    asm.mov(rcx, callee)?;
+    codegen_native_call(asm, value_not_a_function as *const () as u64)?;
+
+    asm.set_label(&mut is_function)?;
+    asm.nop()?;
+
+    Ok(())
+}
+
+/// Calls a Rust function from the middle of a block. The stack registers
+/// that the Windows x64 ABI doesn't preserve are saved around the call, and
+/// the callee gets its own shadow space.
+fn codegen_native_call(asm: &mut CodeAssembler, target: u64) -> Result<()> {
+    codegen_native_call_with_stack_arguments(asm, target, 0, 0)
+}
+
+/// Like `codegen_native_call`, for a function taking `count` arguments past
+/// those in registers. They're copied from the frame slots at `frame_offset`
+/// from `rsp` to above the callee's shadow space, where the ABI passes them.
+fn codegen_native_call_with_stack_arguments(
+    asm: &mut CodeAssembler,
+    target: u64,
+    count: usize,
+    frame_offset: usize,
+) -> Result<()> {
+    // a multiple of 16 keeps the stack aligned for the callee
+    let call_area_size = 32 + (8 * count).next_multiple_of(16);
+
+    asm.push(r10)?;
+    asm.push(r11)?;
+    asm.sub(rsp, call_area_size as i32)?;
+
+    let offset = 16 + call_area_size + frame_offset;
+    for index in 0..count {
+        asm.mov(rax, rsp + (offset + 8 * index))?;
+        asm.mov(rsp + (32 + 8 * index), rax)?;
+    }
+
+    asm.call(target)?;
+    asm.add(rsp, call_area_size as i32)?;
+    asm.pop(r11)?;
+    asm.pop(r10)?;
+
+    Ok(())
+}
+
+/// Moves the arguments of a call from the frame slots they were stored in,
+/// at `frame_offset` from `rsp`, to where the callee expects them: the first
+/// ones in the argument registers and the rest in an area reserved at the
+/// bottom of the stack, with their count in the argument count register.
+/// Returns the size of that area, which the caller releases after the call.
+fn codegen_call_arguments(
+    asm: &mut CodeAssembler,
+    arguments: CallArguments,
+    frame_offset: usize,
+) -> Result<usize> {
+    // a multiple of 16 keeps the stack aligned for the callee
+    let stack_argument_count = arguments.count.saturating_sub(REGISTER_ARGUMENT_COUNT);
+    let stack_arguments_size = (8 * stack_argument_count).next_multiple_of(16);
+    if stack_arguments_size > 0 {
+        asm.sub(rsp, stack_arguments_size as i32)?;
+    }
+
+    let offset = stack_arguments_size + frame_offset + arguments.offset;
+
+    // the argument registers are free until the first arguments are moved
+    // into them
+    for stack_index in 0..stack_argument_count {
+        let argument_offset = offset + 8 * (REGISTER_ARGUMENT_COUNT + stack_index);
+        asm.mov(rcx, rsp + argument_offset)?;
+        asm.mov(rsp + 8 * stack_index, rcx)?;
+    }
+
+    for (index, register) in ARGUMENT_REGISTERS.iter().take(arguments.count).enumerate() {
+        asm.mov(*register, rsp + (offset + 8 * index))?;
+    }
+
+    asm.mov(ARGUMENT_COUNT_REGISTER, arguments.count as u64)?;
+
+    Ok(stack_arguments_size)
+}
+
+/// Reports an error raised by compiled code, and exits. Nothing can catch
+/// the error, and unwinding through compiled code isn't possible.
+fn runtime_error(message: String) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+/// Called when compiled code reads a `let` or `const` binding before its
+/// declaration has run.
+extern "win64" fn binding_not_initialized(name: u64) {
+    runtime_error(format!(
+        "ReferenceError: Cannot access '{}' before initialization",
+        JsValue::from_bits(name).to_display_string()
+    ));
+}
+
+/// Called when compiled code assigns to a `const` binding, where that
+/// isn't caught at compile time.
+extern "win64" fn assignment_to_constant() {
+    runtime_error("TypeError: Assignment to constant variable.".to_string());
+}
+
+/// Pushes an object holding the arguments of the call from index `start`
+/// on, which is the `arguments` object for 0 and a rest parameter otherwise.
+fn codegen_arguments_object(
+    asm: &mut CodeAssembler,
+    context: &mut CodegenContext,
+    extra_stack: usize,
+    start: usize,
+) -> Result<()> {
+    asm.lea(rcx, rsp + context.parameter_offset(0, extra_stack))?;
+    asm.lea(
+        rdx,
+        rsp + context.parameter_offset(REGISTER_ARGUMENT_COUNT, extra_stack),
+    )?;
+    asm.mov(r8, rsp + context.argument_count_offset())?;
+    asm.mov(r9, start as u64)?;
+    codegen_native_call(asm, create_arguments_object as *const () as u64)?;
+
+    let register = context.push(CodegenStackEntry::Dynamic);
+    asm.mov(register, rax)?;
+
+    Ok(())
+}
+
+/// Loads into `register` the environment record `depth` links up from the
+/// innermost one visible in the function.
+fn codegen_environment_record(
+    asm: &mut CodeAssembler,
+    context: &CodegenContext,
+    register: AsmRegister64,
+    depth: usize,
+) -> Result<()> {
+    asm.mov(register, rsp + context.context_offset())?;
+    for _ in 0..depth {
+        asm.mov(register, qword_ptr(register))?;
+    }
+
+    Ok(())
+}
+
+/// The offset of a variable's slot in an environment record, which starts
+/// with the link to the enclosing record.
+fn environment_slot_offset(slot: usize) -> usize {
+    8 * (slot + 1)
+}
+
+/// Allocates the environment record of a function call, linked to `parent`.
+/// Closures can outlive the call, so records are never freed.
+extern "win64" fn allocate_environment_record(parent: u64, size: u64) -> u64 {
+    let mut record = vec![0u64; size as usize + 1];
+    record[0] = parent;
+    Box::leak(record.into_boxed_slice()).as_ptr() as u64
+}
+
+/// Copies the `size` slots of an environment record into a new one, with
+/// the same link to its enclosing record.
+extern "win64" fn copy_environment_record(record: u64, size: u64) -> u64 {
+    let record = unsafe { std::slice::from_raw_parts(record as *const u64, size as usize + 1) };
+    Box::leak(record.to_vec().into_boxed_slice()).as_ptr() as u64
+}
+
+/// Allocates a function object, which pairs the code of a function with the
+/// environment record it was created in, and returns it as a function
+/// value. Like environment records, function objects are never freed.
+extern "win64" fn allocate_function_object(code: u64, context: u64) -> u64 {
+    let object = Box::new([code, context]);
+    TAG_FUNCTION | Box::leak(object).as_ptr() as u64
+}
+
+/// Converts a tagged value to a number.
+extern "win64" fn value_to_number(value: u64) -> u64 {
+    box_number(JsValue::from_bits(value).to_number())
+}
+
+extern "win64" fn value_is_truthy(value: u64) -> u64 {
+    JsValue::from_bits(value).is_truthy() as u64
+}
+
+/// The `typeof` operator on a value of unknown type.
+extern "win64" fn value_type_of(value: u64) -> u64 {
+    intern_string(JsValue::from_bits(value).type_name())
+}
+
+extern "win64" fn value_strict_equals(left: u64, right: u64) -> u64 {
+    JsValue::from_bits(left).strict_equals(JsValue::from_bits(right)) as u64
+}
+
+extern "win64" fn value_loose_equals(left: u64, right: u64) -> u64 {
+    JsValue::from_bits(left).loose_equals(JsValue::from_bits(right)) as u64
+}
+
+/// Orders two values for the relational operators: two strings compare by
+/// their UTF-16 code units, and anything else as numbers. Returns -1, 0 or 1
+/// as a double, or NaN if the values are unordered.
+extern "win64" fn value_compare(left: u64, right: u64) -> u64 {
+    let ordering = match (JsValue::from_bits(left), JsValue::from_bits(right)) {
+        (JsValue::String(left), JsValue::String(right)) => {
+            Some(left.encode_utf16().cmp(right.encode_utf16()))
+        }
+        (left, right) => left.to_number().partial_cmp(&right.to_number()),
+    };
+
+    ordering
+        .map_or(f64::NAN, |ordering| ordering as i8 as f64)
+        .to_bits()
+}
+
+/// The `+` operator. If either operand is a string, once functions and
+/// objects have been converted to strings, the operands are concatenated into
+/// a new string.
+extern "win64" fn value_add(left: u64, right: u64) -> u64 {
+    let (left, right) = (JsValue::from_bits(left), JsValue::from_bits(right));
+    let concatenates = |value: JsValue| {
+        matches!(
+            value,
+            JsValue::String(_) | JsValue::Function(_) | JsValue::Object(_)
+        )
+    };
+
+    if concatenates(left) || concatenates(right) {
+        allocate_string(left.to_display_string() + &right.to_display_string())
+    } else {
+        box_number(left.to_number() + right.to_number())
+    }
+}
+
+/// Allocates an object of shape `shape`, whose slots compiled code fills in.
+extern "win64" fn allocate_object(shape: u64) -> u64 {
+    Object::allocate(ShapeId(shape as usize))
+}
+
+extern "win64" fn value_get_property(value: u64, name: u64) -> u64 {
+    get_property(value, unsafe { string_value(name) })
+}
+
+/// Reads a property whose name is computed at runtime, which converts it to
+/// a string.
+extern "win64" fn value_get_computed_property(value: u64, key: u64) -> u64 {
+    get_property(value, &JsValue::from_bits(key).to_display_string())
+}
+
+extern "win64" fn value_set_property(object: u64, name: u64, value: u64) {
+    set_property(object, unsafe { string_value(name) }, value);
+}
+
+extern "win64" fn value_set_computed_property(object: u64, key: u64, value: u64) {
+    set_property(object, &JsValue::from_bits(key).to_display_string(), value);
+}
+
+/// The miss handler of a property load's inline cache.
+extern "win64" fn inline_cache_load_miss(cache: *mut InlineCache, object: u64) -> u64 {
+    let cache = unsafe { &mut *cache };
+    record_inline_cache_miss(cache, object);
+    get_property(object, cache.property())
+}
+
+/// The miss handler of a property store's inline cache. The slot is cached
+/// for the object's shape before the store, which doesn't change it unless
+/// the property is added, and then there's no slot to cache.
+extern "win64" fn inline_cache_store_miss(cache: *mut InlineCache, object: u64, value: u64) {
+    let cache = unsafe { &mut *cache };
+    record_inline_cache_miss(cache, object);
+    set_property(object, cache.property(), value);
+}
+
+fn record_inline_cache_miss(cache: &mut InlineCache, object: u64) {
+    let shape_slot = match JsValue::from_bits(object) {
+        JsValue::Object(_) => {
+            let shape = unsafe { Object::from_value(object) }.shape();
+            shape.slot(cache.property()).map(|slot| (shape, slot))
+        }
+        _ => None,
+    };
+
+    cache.record_miss(shape_slot);
+}
+
+/// Reads a property of a value. Objects have the properties they've been
+/// given and strings their `length`, in UTF-16 code units. Anything else
+/// reads as undefined, since there are no prototypes yet.
+fn get_property(value: u64, name: &str) -> u64 {
+    match JsValue::from_bits(value) {
+        JsValue::Object(_) => {
+            let object = unsafe { Object::from_value(value) };
+            object.get(name).unwrap_or(UNDEFINED)
+        }
+        JsValue::String(string) if name == "length" => {
+            box_number(string.encode_utf16().count() as f64)
+        }
+        value @ (JsValue::Undefined | JsValue::Null) => runtime_error(format!(
+            "TypeError: Cannot read properties of {} (reading '{}')",
+            value.to_display_string(),
+            name
+        )),
+        _ => UNDEFINED,
+    }
+}
+
+/// Sets a property of an object, adding it if needed. Setting a property of
+/// any other value has no effect.
+fn set_property(object: u64, name: &str, value: u64) {
+    match JsValue::from_bits(object) {
+        JsValue::Object(_) => unsafe { Object::from_value(object) }.set(name, value),
+        object @ (JsValue::Undefined | JsValue::Null) => runtime_error(format!(
+            "TypeError: Cannot set properties of {} (setting '{}')",
+            object.to_display_string(),
+            name
+        )),
+        _ => {}
+    }
+}
+
+/// Creates the `arguments` object of a call, or its rest parameter, from
+/// the arguments from index `start` on. Those that came in registers were
+/// stored next to each other by the prologue, and the others were passed on
+/// the stack.
+extern "win64" fn create_arguments_object(
+    register_arguments: *const u64,
+    stack_arguments: *const u64,
+    count: u64,
+    start: u64,
+) -> u64 {
+    let value = Object::allocate(ShapeId::EMPTY);
+    let object = unsafe { Object::from_value(value) };
+
+    let start = start as usize;
+    let count = count as usize;
+    for index in start..count {
+        let argument = match index.checked_sub(REGISTER_ARGUMENT_COUNT) {
+            None => unsafe { *register_arguments.add(index) },
+            Some(stack_index) => unsafe { *stack_arguments.add(stack_index) },
+        };
+        object.set(&(index - start).to_string(), argument);
+    }
+    object.set("length", box_number(count.saturating_sub(start) as f64));
+
+    value
+}
+
+/// Called when compiled code calls a value that isn't a function.
+extern "win64" fn value_not_a_function(value: u64) {
+    runtime_error(format!(
+        "TypeError: {} is not a function",
+        JsValue::from_bits(value).to_display_string()
+    ));
+}
+
+/// The `%` operator, which takes the sign of the dividend like `fmod`.
+extern "win64" fn number_remainder(dividend: f64, divisor: f64) -> f64 {
+    dividend % divisor
+}
+
+/// The `**` operator, which differs from `powf` when the result could be
+/// either 1 or NaN.
+extern "win64" fn number_power(base: f64, exponent: f64) -> f64 {
+    if exponent.is_nan() || (base.abs() == 1.0 && exponent.is_infinite()) {
+        f64::NAN
+    } else {
+        base.powf(exponent)
+    }
+}
+
 extern "win64" fn basic_block_trampoline(
     environment: *mut Environment,
     basic_block_id: usize,
+    type_info: *const TypeInfo,
 ) -> u64 {
     let environment = unsafe { &mut *environment as &mut Environment };
     let basic_block_id = BasicBlockId(basic_block_id);
-    let type_info = TypeInfo;
-
-    println!("Entered trampoline for basic block {:?}", basic_block_id);
+    let type_info = unsafe { &*type_info }.clone();
 
     let basic_block_fn = environment.compile_basic_block(&basic_block_id, &type_info);
-    basic_block_fn as u64
+    basic_block_fn as usize as u64
 }
@@ -0,0 +1,116 @@
+use almond::ast::BinaryOperator;
+use anyhow::Result;
+use iced_x86::code_asm::{al, AsmRegister8, CodeAssembler};
+
+/// The outcome of a comparison, as a condition on the processor flags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+    // signed integer comparisons, after `cmp`
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+
+    // double comparisons, after `ucomisd`
+    OrderedEqual,
+    UnorderedOrNotEqual,
+    Above,
+    AboveOrEqual,
+}
+
+impl Condition {
+    /// The condition for comparing two integers with `operator`.
+    pub fn for_integers(operator: BinaryOperator) -> Option<Self> {
+        match operator {
+            BinaryOperator::EqualsEquals | BinaryOperator::TripleEquals => Some(Condition::Equal),
+            BinaryOperator::NotEquals | BinaryOperator::TripleNotEquals => {
+                Some(Condition::NotEqual)
+            }
+            BinaryOperator::LessThan => Some(Condition::Less),
+            BinaryOperator::LessThanEquals => Some(Condition::LessOrEqual),
+            BinaryOperator::GreaterThan => Some(Condition::Greater),
+            BinaryOperator::GreaterThanEquals => Some(Condition::GreaterOrEqual),
+            _ => None,
+        }
+    }
+
+    /// The condition for comparing two doubles with `operator`, and whether
+    /// the operands have to be swapped. A comparison involving NaN is
+    /// unordered, which leaves the `Above` conditions false, so `<` and `<=`
+    /// are compiled as `>` and `>=` on swapped operands.
+    pub fn for_numbers(operator: BinaryOperator) -> Option<(Self, bool)> {
+        match operator {
+            BinaryOperator::EqualsEquals | BinaryOperator::TripleEquals => {
+                Some((Condition::OrderedEqual, false))
+            }
+            BinaryOperator::NotEquals | BinaryOperator::TripleNotEquals => {
+                Some((Condition::UnorderedOrNotEqual, false))
+            }
+            BinaryOperator::LessThan => Some((Condition::Above, true)),
+            BinaryOperator::LessThanEquals => Some((Condition::AboveOrEqual, true)),
+            BinaryOperator::GreaterThan => Some((Condition::Above, false)),
+            BinaryOperator::GreaterThanEquals => Some((Condition::AboveOrEqual, false)),
+            _ => None,
+        }
+    }
+
+    /// Sets `register` to 1 if the condition holds, or to 0 otherwise.
+    /// Clobbers `al`. Conditions on two flags have to combine them, which
+    /// changes the flags, so this returns the condition that holds afterwards.
+    pub fn set(self, asm: &mut CodeAssembler, register: AsmRegister8) -> Result<Condition> {
+        match self {
+            Condition::Equal => asm.sete(register)?,
+            Condition::NotEqual => asm.setne(register)?,
+            Condition::Less => asm.setl(register)?,
+            Condition::LessOrEqual => asm.setle(register)?,
+            Condition::Greater => asm.setg(register)?,
+            Condition::GreaterOrEqual => asm.setge(register)?,
+
+            Condition::OrderedEqual => {
+                asm.sete(register)?;
+                asm.setnp(al)?;
+                asm.and(register, al)?;
+                return Ok(Condition::NotEqual);
+            }
+            Condition::UnorderedOrNotEqual => {
+                asm.setne(register)?;
+                asm.setp(al)?;
+                asm.or(register, al)?;
+                return Ok(Condition::NotEqual);
+            }
+            Condition::Above => asm.seta(register)?,
+            Condition::AboveOrEqual => asm.setae(register)?,
+        }
+
+        Ok(self)
+    }
+
+    /// Jumps to `then_target` if the condition holds, or to `else_target`
+    /// otherwise.
+    pub fn branch(self, asm: &mut CodeAssembler, then_target: u64, else_target: u64) -> Result<()> {
+        match self {
+            Condition::Equal => asm.je(then_target)?,
+            Condition::NotEqual => asm.jne(then_target)?,
+            Condition::Less => asm.jl(then_target)?,
+            Condition::LessOrEqual => asm.jle(then_target)?,
+            Condition::Greater => asm.jg(then_target)?,
+            Condition::GreaterOrEqual => asm.jge(then_target)?,
+
+            Condition::OrderedEqual => {
+                asm.jp(else_target)?;
+                asm.je(then_target)?;
+            }
+            Condition::UnorderedOrNotEqual => {
+                asm.jp(then_target)?;
+                asm.jne(then_target)?;
+            }
+            Condition::Above => asm.ja(then_target)?,
+            Condition::AboveOrEqual => asm.jae(then_target)?,
+        }
+
+        asm.jmp(else_target)?;
+        Ok(())
+    }
+}
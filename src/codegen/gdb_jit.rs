@@ -54,7 +54,7 @@ lazy_static! {
     //
     // The GDB_REGISTRATION lock is needed for GdbJitImageRegistration to protect
     // access to the __jit_debug_descriptor within this process.
-    pub static ref GDB_REGISTRATION: Mutex<()> = Mutex::new(Default::default());
+    pub static ref GDB_REGISTRATION: Mutex<()> = Mutex::new(());
 }
 
 /// Registeration for JIT image
@@ -86,7 +86,7 @@ impl GdbJitImageRegistration {
 
     /// JIT image used in registration
     pub fn file(&self) -> &[u8] {
-        &*self.file
+        &self.file
     }
 }
 
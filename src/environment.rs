@@ -5,18 +5,34 @@ use memmap::Mmap;
 
 use crate::{
     codegen::{
-        codegen_basic_block, codegen_trampoline, gdb_jit::GdbJitImageRegistration, UnaryFunction,
+        codegen_basic_block, codegen_inline_cache_stub, codegen_trampoline,
+        gdb_jit::GdbJitImageRegistration, UnaryFunction,
     },
     flow_graph::{BasicBlock, BasicBlockId, FlowGraph},
+    inline_cache::{InlineCache, PropertyAccess},
+    CodegenStackEntry,
 };
 
-#[derive(Clone, PartialEq, Eq, Hash)]
-pub struct TypeInfo;
+/// The types of the values on the codegen stack when a block is entered.
+/// A block is compiled separately for each combination it's entered with,
+/// so the paths joining at a block can leave values of different types.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TypeInfo {
+    pub stack: Vec<CodegenStackEntry>,
+}
+
+type Trampoline = (GdbJitImageRegistration, Box<TypeInfo>, UnaryFunction);
 
 pub struct Environment<'a> {
     dump_disassembly: bool,
     block_versions: HashMap<(BasicBlockId, TypeInfo), (Mmap, UnaryFunction)>,
-    trampolines: HashMap<(BasicBlockId, TypeInfo), (GdbJitImageRegistration, UnaryFunction)>,
+    trampolines: HashMap<(BasicBlockId, TypeInfo), Trampoline>,
+
+    /// Compiled code points into the boxes, so they stay put.
+    #[allow(clippy::vec_box)]
+    inline_caches: Vec<Box<InlineCache>>,
+    inline_cache_stubs: HashMap<PropertyAccess, Mmap>,
+
     pub flow_graph: FlowGraph<'a>,
 }
 
@@ -26,11 +42,13 @@ impl<'a> Environment<'a> {
             dump_disassembly,
             block_versions: HashMap::new(),
             trampolines: HashMap::new(),
+            inline_caches: Vec::new(),
+            inline_cache_stubs: HashMap::new(),
             flow_graph,
         }
     }
 
-    pub fn get_basic_block(&self, basic_block_id: &BasicBlockId) -> Option<&BasicBlock> {
+    pub fn get_basic_block(&self, basic_block_id: &BasicBlockId) -> Option<&BasicBlock<'a>> {
         self.flow_graph.get_basic_block(basic_block_id)
     }
 
@@ -45,14 +63,52 @@ impl<'a> Environment<'a> {
             return *block_fn;
         }
 
-        let trampoline_result = codegen_trampoline(self, &basic_block_id, self.dump_disassembly)
-            .expect("codegen failed");
-        self.trampolines.insert(key.clone(), trampoline_result);
-        self.trampolines.get(&key).unwrap().1
+        // compiled code may still refer to an existing trampoline, so it must
+        // be reused rather than replaced
+        if let Some((_registration, _type_info, trampoline_fn)) = self.trampolines.get(&key) {
+            return *trampoline_fn;
+        }
+
+        // the trampoline refers to the boxed type info, which stays put
+        let type_info = Box::new(key.1.clone());
+        let (registration, trampoline_fn) =
+            codegen_trampoline(self, &basic_block_id, &type_info, self.dump_disassembly)
+                .expect("codegen failed");
+        self.trampolines
+            .insert(key, (registration, type_info, trampoline_fn));
+        trampoline_fn
+    }
+
+    /// The inline caches of the property accesses compiled so far, with the
+    /// number of hits and misses each has had.
+    pub fn inline_caches(&self) -> impl Iterator<Item = &InlineCache> {
+        self.inline_caches.iter().map(Box::as_ref)
     }
 
-    pub fn run(mut self) -> Result<()> {
-        let type_info = TypeInfo;
+    pub(crate) fn create_inline_cache(
+        &mut self,
+        basic_block_id: BasicBlockId,
+        access: PropertyAccess,
+        property: String,
+    ) -> *mut InlineCache {
+        let mut cache = Box::new(InlineCache::new(basic_block_id, access, property));
+        let pointer = cache.as_mut() as *mut InlineCache;
+        self.inline_caches.push(cache);
+        pointer
+    }
+
+    /// The address of the code shared by the inline caches of property
+    /// accesses of one kind, which is generated the first time it's needed.
+    pub(crate) fn inline_cache_stub(&mut self, access: PropertyAccess) -> u64 {
+        let dump_disassembly = self.dump_disassembly;
+        let stub = self.inline_cache_stubs.entry(access).or_insert_with(|| {
+            codegen_inline_cache_stub(access, dump_disassembly).expect("codegen failed")
+        });
+        stub.as_ptr() as u64
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        let type_info = TypeInfo::default();
         let basic_block_id = self.flow_graph.root_block_id.expect("no root block");
 
         let block_fn = self.basic_block_fn(basic_block_id, type_info);
@@ -66,8 +122,21 @@ impl<'a> Environment<'a> {
         basic_block_id: &BasicBlockId,
         type_info: &TypeInfo,
     ) -> UnaryFunction {
-        let mmap =
-            codegen_basic_block(self, basic_block_id, self.dump_disassembly).expect("codegen");
+        // a trampoline keeps entering the compiler until the code that jumps
+        // to it is regenerated, so the block may already have been compiled
+        if let Some((_mmap, block_fn)) = self
+            .block_versions
+            .get(&(*basic_block_id, type_info.clone()))
+        {
+            return *block_fn;
+        }
+
+        if self.dump_disassembly {
+            println!("Compiling basic block {:?}", basic_block_id);
+        }
+
+        let mmap = codegen_basic_block(self, basic_block_id, type_info, self.dump_disassembly)
+            .expect("codegen");
         let entry_fn: extern "win64" fn() = unsafe { mem::transmute(mmap.as_ptr()) };
 
         self.block_versions
@@ -1,5 +1,8 @@
 mod basic_block;
+mod captures;
 mod flow_instruction;
+mod hoisting;
+mod jump_target;
 mod nodes;
 mod scope;
 mod tail_instruction;
@@ -8,47 +11,90 @@ mod value;
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use almond::ast::{Node, NodeKind};
+use anyhow::{bail, Result};
 
 use crate::{
     flow_graph::nodes::{
-        expression_statement::handle_expression_statement, for_statement::handle_for_statement,
-        function_decl::handle_function_decl, return_statement::handle_return_statement,
+        block_statement::handle_block_statement, break_statement::handle_break_statement,
+        continue_statement::handle_continue_statement,
+        do_while_statement::handle_do_while_statement,
+        expression::evaluate_expression, expression_statement::handle_expression_statement,
+        for_statement::handle_for_statement,
+        function_decl::handle_function_decl, if_statement::handle_if_statement,
+        labeled_statement::handle_labeled_statement, return_statement::handle_return_statement,
+        variable_declaration::handle_variable_declarations,
+        while_statement::handle_while_statement,
     },
     source_location::SourceLocation,
 };
 
+use self::{hoisting::hoist_declarations, jump_target::JumpTarget};
+
 pub use self::{
     basic_block::{BasicBlock, BasicBlockId},
-    flow_instruction::FlowInstruction,
+    flow_instruction::{CallArguments, FlowInstruction},
     tail_instruction::TailInstruction,
     scope::{Id, Scope},
     value::{SystemFunction, SystemFunctionGeneratorFn, SystemFunctionHandlerFn, Value},
 };
 
+/// What `enter_function` puts aside while the body of a function is
+/// compiled: the function's scope, and the state of the code around it.
+struct EnclosingFunction {
+    scope: Rc<RefCell<Scope>>,
+    jump_targets: Vec<JumpTarget>,
+    return_block: Option<BasicBlockId>,
+}
+
 #[derive(Default, Debug)]
 pub struct FlowGraph<'a> {
     pub root_block_id: Option<BasicBlockId>,
     next_basic_block_id: BasicBlockId,
     basic_blocks: HashMap<BasicBlockId, BasicBlock<'a>>,
+
+    /// Targets of `break` and `continue` for the statements enclosing the
+    /// one being compiled, innermost last.
+    jump_targets: Vec<JumpTarget>,
+
+    /// Labels of the labeled statements directly enclosing the next loop.
+    pending_labels: Vec<Id>,
+
+    /// The block holding the epilogue of the function being compiled, which
+    /// every `return` jumps to.
+    return_block: Option<BasicBlockId>,
 }
 
 impl<'a> FlowGraph<'a> {
-    pub fn from_root_node(node: &'a Node, scope: Scope) -> Self {
+    pub fn from_root_node(node: &'a Node, scope: Scope) -> Result<Self> {
         match &node.kind {
             NodeKind::Program { body } => {
                 let mut graph = FlowGraph::default();
 
+                // the global scope has `undefined`, unless it's been given
+                // a binding of that name
+                let mut scope = scope;
+                let undefined = Id::new("undefined");
+                if !scope.has_binding(&undefined) {
+                    scope.insert(undefined, Value::Undefined);
+                }
+
                 let scope = Rc::new(RefCell::new(scope));
-                let root_block_id = graph.create_basic_block(node, scope, body, true);
+                let root_block_id = graph.create_basic_block(node, scope, body, true)?;
 
                 graph.root_block_id = Some(root_block_id);
-                graph
+                Ok(graph)
             }
 
             node_kind => todo!("insert node into flow graph {:?}", node_kind),
         }
     }
 
+    /// Allocates the id of a block that is created later, with
+    /// `create_basic_block_with_id`.
+    pub(crate) fn reserve_basic_block_id(&mut self) -> BasicBlockId {
+        self.next_basic_block_id()
+    }
+
     fn next_basic_block_id(&mut self) -> BasicBlockId {
         let id = self.next_basic_block_id;
         self.next_basic_block_id = BasicBlockId(id.0 + 1);
@@ -59,27 +105,189 @@ impl<'a> FlowGraph<'a> {
         self.basic_blocks.insert(block.id, block);
     }
 
-    pub(crate) fn add_node_to_block(&mut self, block: &mut BasicBlock<'a>, node: &Node<'a>) {
+    pub(crate) fn add_node_to_block(
+        &mut self,
+        block: &mut BasicBlock<'a>,
+        node: &Node<'a>,
+    ) -> Result<()> {
         match &node.kind {
             NodeKind::FunctionDeclaration { function } => {
-                handle_function_decl(self, block, function)
+                handle_function_decl(self, block, function)?
             }
             NodeKind::ExpressionStatement {
                 expression,
                 directive: _,
-            } => handle_expression_statement(block, expression),
+            } => handle_expression_statement(self, block, expression)?,
 
-            NodeKind::ReturnStatement { argument } => handle_return_statement(block, argument),
+            NodeKind::ReturnStatement { argument } => {
+                handle_return_statement(self, node, block, argument)?
+            }
+
+            NodeKind::VariableDeclaration { declarations, kind } => {
+                handle_variable_declarations(self, block, declarations, kind)?
+            }
 
             NodeKind::ForStatement {
                 body,
                 init,
                 test,
                 update,
-            } => handle_for_statement(self, node, block, init, test, update, body),
+            } => handle_for_statement(self, node, block, init, test, update, body)?,
+
+            NodeKind::WhileStatement { test, body } => {
+                handle_while_statement(self, node, block, test, body)?
+            }
+
+            NodeKind::DoWhileStatement { body, test } => {
+                handle_do_while_statement(self, node, block, body, test)?
+            }
+
+            NodeKind::IfStatement {
+                test,
+                consequent,
+                alternate,
+            } => handle_if_statement(self, node, block, test, consequent, alternate)?,
+
+            NodeKind::LabeledStatement { label, body } => {
+                handle_labeled_statement(self, node, block, label, body)?
+            }
+
+            NodeKind::BreakStatement { label } => handle_break_statement(self, node, block, label)?,
+
+            NodeKind::ContinueStatement { label } => {
+                handle_continue_statement(self, node, block, label)?
+            }
+
+            NodeKind::BlockStatement { body } => handle_block_statement(self, block, body)?,
+
+            NodeKind::EmptyStatement => {}
 
             kind => todo!("compile node {:?}", kind),
         }
+
+        Ok(())
+    }
+
+    /// Makes `break_block` and `continue_block` the targets of `break` and
+    /// `continue` statements until the matching `pop_jump_target`. The target
+    /// takes over the labels of the labeled statements around it. Both blocks
+    /// continue in `scope`.
+    pub(crate) fn push_jump_target(
+        &mut self,
+        scope: Rc<RefCell<Scope>>,
+        break_block: BasicBlockId,
+        continue_block: Option<BasicBlockId>,
+    ) {
+        let labels = std::mem::take(&mut self.pending_labels);
+        self.jump_targets.push(JumpTarget {
+            labels,
+            break_block,
+            continue_block,
+            scope,
+        });
+    }
+
+    pub(crate) fn pop_jump_target(&mut self) {
+        self.jump_targets
+            .pop()
+            .expect("jump target stack underflow");
+    }
+
+    /// Labels the next statement that pushes a jump target.
+    pub(crate) fn push_pending_label(&mut self, label: Id) {
+        self.pending_labels.push(label);
+    }
+
+    fn labeled_jump_target(&self, label: &Id) -> Result<&JumpTarget> {
+        match self
+            .jump_targets
+            .iter()
+            .rev()
+            .find(|target| target.labels.contains(label))
+        {
+            Some(target) => Ok(target),
+            None => bail!("SyntaxError: Undefined label '{}'", label.0),
+        }
+    }
+
+    /// The block that a `break` jumps to, and the scope it continues in.
+    pub(crate) fn break_target(
+        &self,
+        label: Option<&Id>,
+    ) -> Result<(BasicBlockId, Rc<RefCell<Scope>>)> {
+        if let Some(label) = label {
+            let target = self.labeled_jump_target(label)?;
+            return Ok((target.break_block, target.scope.clone()));
+        }
+
+        match self
+            .jump_targets
+            .iter()
+            .rev()
+            .find(|target| target.continue_block.is_some())
+        {
+            Some(target) => Ok((target.break_block, target.scope.clone())),
+            None => bail!("SyntaxError: Illegal break statement"),
+        }
+    }
+
+    /// The block that a `return` in the function being compiled jumps to.
+    pub(crate) fn return_target(&self) -> BasicBlockId {
+        self.return_block.expect("return outside of a function")
+    }
+
+    /// The block that a `continue` jumps to, and the scope it continues in.
+    pub(crate) fn continue_target(
+        &self,
+        label: Option<&Id>,
+    ) -> Result<(BasicBlockId, Rc<RefCell<Scope>>)> {
+        if let Some(label) = label {
+            let target = self.labeled_jump_target(label)?;
+            return match target.continue_block {
+                Some(continue_block) => Ok((continue_block, target.scope.clone())),
+                None => bail!(
+                    "SyntaxError: Illegal continue statement: '{}' does not denote an iteration statement",
+                    label.0
+                ),
+            };
+        }
+
+        match self.jump_targets.iter().rev().find_map(|target| {
+            target
+                .continue_block
+                .map(|continue_block| (continue_block, target.scope.clone()))
+        }) {
+            Some(target) => Ok(target),
+            None => {
+                bail!("SyntaxError: Illegal continue statement: no surrounding iteration statement")
+            }
+        }
+    }
+
+    /// Allocates a new, empty basic block. The block is not part of the graph
+    /// until it is passed to `insert_block` or `switch_to_block`.
+    pub(crate) fn new_basic_block(
+        &mut self,
+        parent: &Node<'a>,
+        scope: Rc<RefCell<Scope>>,
+    ) -> BasicBlock<'a> {
+        let id = self.next_basic_block_id();
+        BasicBlock::new(
+            id,
+            scope,
+            SourceLocation {
+                start: parent.start,
+                end: parent.end,
+            },
+        )
+    }
+
+    /// Finishes `block` by inserting it into the graph, and continues
+    /// compilation in `next`. Nodes that alter control flow use this so that
+    /// the statements following them end up in their continuation block.
+    pub(crate) fn switch_to_block(&mut self, block: &mut BasicBlock<'a>, next: BasicBlock<'a>) {
+        let finished = std::mem::replace(block, next);
+        self.insert_block(finished);
     }
 
     pub fn create_basic_block(
@@ -88,9 +296,23 @@ impl<'a> FlowGraph<'a> {
         scope: Rc<RefCell<Scope>>,
         nodes: &[Node<'a>],
         include_prologue_epilogue: bool,
-        
-    ) -> BasicBlockId {
+    ) -> Result<BasicBlockId> {
         let id = self.next_basic_block_id();
+        self.create_basic_block_with_id(id, parent, scope, &[], nodes, include_prologue_epilogue)
+    }
+
+    /// Like `create_basic_block`, for an id from `reserve_basic_block_id`.
+    /// A block with a prologue is the body of a function with the parameters
+    /// `params`, or of the program, whose declarations are hoisted.
+    pub(crate) fn create_basic_block_with_id(
+        &mut self,
+        id: BasicBlockId,
+        parent: &Node<'a>,
+        scope: Rc<RefCell<Scope>>,
+        params: &[Node<'a>],
+        nodes: &[Node<'a>],
+        include_prologue_epilogue: bool,
+    ) -> Result<BasicBlockId> {
         let mut block = BasicBlock::new(
             id,
             scope,
@@ -98,23 +320,113 @@ impl<'a> FlowGraph<'a> {
                 start: parent.start,
                 end: parent.end,
             },
-            //next_basic_block_id,
         );
 
-        if include_prologue_epilogue {
-            block.instructions.push(FlowInstruction::FunctionPrologue);
-        }
+        let enclosing_function = if include_prologue_epilogue {
+            Some(self.enter_function(&mut block, params, nodes)?)
+        } else {
+            None
+        };
 
         for node in nodes {
-            self.add_node_to_block(&mut block, node);
+            let hoisted = matches!(node.kind, NodeKind::FunctionDeclaration { .. });
+            if include_prologue_epilogue && hoisted {
+                continue;
+            }
+
+            self.add_node_to_block(&mut block, node)?;
         }
 
-        if include_prologue_epilogue {
-            block.instructions.push(FlowInstruction::FunctionEpilogue);
+        if let Some(enclosing_function) = enclosing_function {
+            self.leave_function(&mut block, parent, enclosing_function);
         }
 
         self.insert_block(block);
-        id
+        Ok(id)
+    }
+
+    /// Like `create_basic_block_with_id` for a function body, for the
+    /// concise body of an arrow function: an expression that the function
+    /// returns the value of.
+    pub(crate) fn create_concise_body_block_with_id(
+        &mut self,
+        id: BasicBlockId,
+        body: &Node<'a>,
+        scope: Rc<RefCell<Scope>>,
+        params: &[Node<'a>],
+    ) -> Result<BasicBlockId> {
+        let mut block = BasicBlock::new(
+            id,
+            scope,
+            SourceLocation {
+                start: body.start,
+                end: body.end,
+            },
+        );
+
+        let enclosing_function = self.enter_function(&mut block, params, std::slice::from_ref(body))?;
+
+        evaluate_expression(self, &mut block, body)?;
+        block.push(FlowInstruction::ReturnValue);
+        block.tails.push(TailInstruction::Jump(self.return_target()));
+
+        // nothing jumps to the block that the epilogue would follow
+        let unreachable_block = self.new_basic_block(body, block.scope.clone());
+        self.switch_to_block(&mut block, unreachable_block);
+
+        self.leave_function(&mut block, body, enclosing_function);
+
+        self.insert_block(block);
+        Ok(id)
+    }
+
+    /// Starts the body of a function in `block`, whose statements are
+    /// `nodes`, by hoisting their declarations. Statements in a function body
+    /// can't jump to the enclosing statements, so their targets are put
+    /// aside, to be restored by `leave_function`.
+    fn enter_function(
+        &mut self,
+        block: &mut BasicBlock<'a>,
+        params: &[Node<'a>],
+        nodes: &[Node<'a>],
+    ) -> Result<EnclosingFunction> {
+        block.instructions.push(FlowInstruction::FunctionPrologue);
+        hoist_declarations(self, block, params, nodes)?;
+
+        let return_block = self.reserve_basic_block_id();
+        Ok(EnclosingFunction {
+            scope: block.scope.clone(),
+            jump_targets: std::mem::take(&mut self.jump_targets),
+            return_block: self.return_block.replace(return_block),
+        })
+    }
+
+    /// Ends the body of a function, which falls off the end in `block`.
+    fn leave_function(
+        &mut self,
+        block: &mut BasicBlock<'a>,
+        parent: &Node<'a>,
+        enclosing_function: EnclosingFunction,
+    ) {
+        // falling off the end of a function returns undefined. The epilogue
+        // has the frame size of the function scope, whichever block returns
+        let return_block = self.return_target();
+        block.instructions.push(FlowInstruction::Return);
+        block.tails.push(TailInstruction::Jump(return_block));
+
+        let mut epilogue = BasicBlock::new(
+            return_block,
+            enclosing_function.scope,
+            SourceLocation {
+                start: parent.start,
+                end: parent.end,
+            },
+        );
+        epilogue.push(FlowInstruction::FunctionEpilogue);
+        self.insert_block(epilogue);
+
+        self.jump_targets = enclosing_function.jump_targets;
+        self.return_block = enclosing_function.return_block;
     }
 
     pub fn get_basic_block(&self, basic_block_id: &BasicBlockId) -> Option<&BasicBlock<'a>> {
@@ -43,7 +43,8 @@ impl<'a> BasicBlock<'a> {
         self.instructions.clone()
     }
 
-    pub fn stack_allocation(&self) -> usize {
-        self.scope.borrow().stack_allocation
+    /// The size of the variables in the frame of the block's function.
+    pub fn frame_size(&self) -> usize {
+        self.scope.borrow().frame_size()
     }
 }
@@ -0,0 +1,301 @@
+use std::collections::HashSet;
+
+use almond::ast::{Node, NodeKind};
+
+use super::Id;
+
+/// Collects the names referred to by the functions nested in `nodes`, which
+/// is every name those functions could capture from the enclosing function.
+/// Shadowing isn't taken into account, so this can include names that the
+/// nested functions declare themselves.
+pub(crate) fn nested_function_references(nodes: &[Node]) -> HashSet<Id> {
+    let mut names = HashSet::new();
+    for node in nodes {
+        collect_nested_references(node, &mut names);
+    }
+    names
+}
+
+/// Whether the code in `nodes` refers to `name` itself, rather than only
+/// through the functions nested in it.
+pub(crate) fn refers_to(nodes: &[Node], name: &Id) -> bool {
+    let mut names = HashSet::new();
+    for node in nodes {
+        collect_own_references(node, &mut names);
+    }
+    names.contains(name)
+}
+
+/// Collects the names that `nodes` assign to, in the functions nested in
+/// them as well. Shadowing isn't taken into account, so this can include
+/// names that only other bindings of the same name are assigned under.
+pub(crate) fn assigned_names(nodes: &[Node]) -> HashSet<Id> {
+    let mut names = HashSet::new();
+    for node in nodes {
+        collect_assigned_names(node, &mut names);
+    }
+    names
+}
+
+fn collect_assigned_names(node: &Node, names: &mut HashSet<Id>) {
+    let target = match &node.kind {
+        NodeKind::AssignmentExpression {
+            operator: _,
+            left,
+            right: _,
+        } => Some(left),
+        NodeKind::UpdateExpression {
+            operator: _,
+            argument,
+            prefix: _,
+        } => Some(argument),
+        NodeKind::VariableDeclarator { id, init } if init.is_some() => Some(id),
+        _ => None,
+    };
+
+    if let Some(NodeKind::Identifier { name }) = target.map(|target| &target.kind) {
+        names.insert(Id::new(name));
+    }
+
+    for_each_child(node, &mut |child| collect_assigned_names(child, names));
+}
+
+/// Arrow functions don't have an `arguments` of their own, so the names they
+/// refer to count as the enclosing function's own.
+fn collect_own_references(node: &Node, names: &mut HashSet<Id>) {
+    match &node.kind {
+        NodeKind::FunctionDeclaration { .. } | NodeKind::FunctionExpression { .. } => {}
+        NodeKind::Identifier { name } => {
+            names.insert(Id::new(name));
+        }
+        _ => for_each_child(node, &mut |child| collect_own_references(child, names)),
+    }
+}
+
+fn collect_nested_references(node: &Node, names: &mut HashSet<Id>) {
+    match &node.kind {
+        NodeKind::FunctionDeclaration { function }
+        | NodeKind::FunctionExpression { function }
+        | NodeKind::ArrowFunctionExpression {
+            function,
+            expression: _,
+        } => {
+            // default parameters run in the function as well
+            function
+                .params
+                .iter()
+                .for_each(|param| collect_identifiers(param, names));
+            collect_identifiers(&function.body, names);
+        }
+        _ => for_each_child(node, &mut |child| collect_nested_references(child, names)),
+    }
+}
+
+fn collect_identifiers(node: &Node, names: &mut HashSet<Id>) {
+    if let NodeKind::Identifier { name } = &node.kind {
+        names.insert(Id::new(name));
+    }
+
+    for_each_child(node, &mut |child| collect_identifiers(child, names));
+}
+
+/// Calls `f` with each of the nodes directly inside `node`.
+fn for_each_child<'n, 'a>(node: &'n Node<'a>, f: &mut dyn FnMut(&'n Node<'a>)) {
+    match &node.kind {
+        NodeKind::Program { body }
+        | NodeKind::BlockStatement { body }
+        | NodeKind::SwitchCase {
+            test: _,
+            consequent: body,
+        } => body.iter().for_each(f),
+
+        NodeKind::ArrayExpression { elements } => elements.iter().flatten().for_each(f),
+        NodeKind::ObjectExpression { properties } => properties.iter().for_each(f),
+        NodeKind::SequenceExpression { expressions } => expressions.iter().for_each(f),
+
+        NodeKind::ExpressionStatement {
+            expression,
+            directive: _,
+        } => f(expression),
+
+        NodeKind::ThrowStatement { argument }
+        | NodeKind::UnaryExpression {
+            operator: _,
+            prefix: _,
+            argument,
+        }
+        | NodeKind::UpdateExpression {
+            operator: _,
+            argument,
+            prefix: _,
+        }
+        | NodeKind::AwaitExpression { argument } => f(argument),
+
+        NodeKind::ReturnStatement { argument } => {
+            if let Some(argument) = &**argument {
+                f(argument);
+            }
+        }
+
+        NodeKind::LabeledStatement { label: _, body } => f(body),
+
+        NodeKind::WithStatement { object, body } => {
+            f(object);
+            f(body);
+        }
+
+        NodeKind::IfStatement {
+            test,
+            consequent,
+            alternate,
+        } => {
+            f(test);
+            f(consequent);
+            if let Some(alternate) = &**alternate {
+                f(alternate);
+            }
+        }
+
+        NodeKind::SwitchStatement {
+            discriminant,
+            cases,
+        } => {
+            f(discriminant);
+            cases.iter().for_each(f);
+        }
+
+        NodeKind::TryStatement {
+            block,
+            handler,
+            finalizer,
+        } => {
+            f(block);
+            if let Some(handler) = &**handler {
+                f(handler);
+            }
+            if let Some(finalizer) = &**finalizer {
+                f(finalizer);
+            }
+        }
+
+        NodeKind::CatchClause { param, body } => {
+            f(param);
+            f(body);
+        }
+
+        NodeKind::WhileStatement { test, body } | NodeKind::DoWhileStatement { body, test } => {
+            f(test);
+            f(body);
+        }
+
+        NodeKind::ForStatement {
+            init,
+            test,
+            update,
+            body,
+        } => {
+            for node in [&**init, &**test, &**update].into_iter().flatten() {
+                f(node);
+            }
+            f(body);
+        }
+
+        NodeKind::ForInStatement { left, right, body } => {
+            f(left);
+            f(right);
+            f(body);
+        }
+
+        NodeKind::FunctionDeclaration { function }
+        | NodeKind::FunctionExpression { function }
+        | NodeKind::ArrowFunctionExpression {
+            function,
+            expression: _,
+        } => {
+            function.params.iter().for_each(&mut *f);
+            f(&function.body);
+        }
+
+        NodeKind::VariableDeclaration {
+            declarations,
+            kind: _,
+        } => declarations.iter().for_each(f),
+
+        NodeKind::AssignmentPattern { left, right } => {
+            f(left);
+            f(right);
+        }
+
+        NodeKind::RestElement { argument } => f(argument),
+
+        NodeKind::VariableDeclarator { id, init } => {
+            f(id);
+            if let Some(init) = &**init {
+                f(init);
+            }
+        }
+
+        // keys are names rather than expressions
+        NodeKind::Property {
+            key: _,
+            value,
+            kind: _,
+        } => f(value),
+
+        NodeKind::BinaryExpression {
+            operator: _,
+            left,
+            right,
+        }
+        | NodeKind::AssignmentExpression {
+            operator: _,
+            left,
+            right,
+        }
+        | NodeKind::LogicalExpression {
+            operator: _,
+            left,
+            right,
+        } => {
+            f(left);
+            f(right);
+        }
+
+        NodeKind::MemberExpression {
+            object,
+            property,
+            computed,
+        } => {
+            f(object);
+            if *computed {
+                f(property);
+            }
+        }
+
+        NodeKind::ConditionalExpression {
+            test,
+            consequent,
+            alternate,
+        } => {
+            f(test);
+            f(consequent);
+            f(alternate);
+        }
+
+        NodeKind::CallExpression { callee, arguments }
+        | NodeKind::NewExpression { callee, arguments } => {
+            f(callee);
+            arguments.iter().for_each(f);
+        }
+
+        NodeKind::Identifier { .. }
+        | NodeKind::Literal { .. }
+        | NodeKind::EmptyStatement
+        | NodeKind::DebuggerStatement
+        | NodeKind::BreakStatement { .. }
+        | NodeKind::ContinueStatement { .. }
+        | NodeKind::ThisExpression
+        | NodeKind::Pattern
+        | NodeKind::Error => {}
+    }
+}
@@ -1,26 +1,93 @@
-use almond::ast::BinaryOperator;
+use almond::ast::{BinaryOperator, UnaryOperator};
 
 use super::{basic_block::BasicBlockId, value::SystemFunction};
 
+/// The arguments of a call, which are stored in the frame from `offset` on
+/// as they're evaluated.
+#[derive(Clone, Copy, Debug)]
+pub struct CallArguments {
+    pub count: usize,
+    pub offset: usize,
+}
+
 #[derive(Clone, Debug)]
 pub enum FlowInstruction {
     FunctionPrologue,
     FunctionEpilogue,
     Assign,
+    /// Like `Assign`, for a `const` binding, which raises a `TypeError`
+    /// instead of assigning.
+    AssignConstant,
     PushLiteralBoolean(bool),
     PushLiteralString(String),
     PushLiteralNumber(f64),
     PushLiteralNull,
+    PushLiteralUndefined,
+    /// Pushes the value that a `let` or `const` binding holds until its
+    /// declaration runs.
+    PushUninitialized,
+    /// Raises a `ReferenceError` if the value on top of the stack, read from
+    /// the `let` or `const` binding named, is still uninitialized.
+    CheckInitialized(String),
     PushFunctionParameter(usize),
     PushStackVariable(usize),
+    PushCapturedVariable {
+        depth: usize,
+        slot: usize,
+    },
+    CreateEnvironmentRecord(usize),
+    /// Replaces the innermost environment record with a copy of its given
+    /// number of slots, so that closures created in one iteration of a loop
+    /// keep the bindings of that iteration.
+    CopyEnvironmentRecord(usize),
+    /// Leaves the environment record of a block, making the one it's linked
+    /// to the innermost again.
+    PopEnvironmentRecord,
+    PushFunction {
+        basic_block_id: BasicBlockId,
+        context_depth: usize,
+    },
+    CreateObject(Vec<String>),
+    GetProperty(String),
+    SetProperty(String),
+    /// Sets a property of an object literal being created: pops the value,
+    /// and leaves the object on the stack for the next one.
+    InitProperty(String),
+    GetComputedProperty,
+    SetComputedProperty,
     ApplyBinaryOperator(BinaryOperator),
+    ApplyUnaryOperator(UnaryOperator),
+    /// Stores an argument at the given frame offset, where the call it's for
+    /// takes it from.
+    StoreArgument(usize),
+    CreateArgumentsObject,
+    /// Creates the value of a rest parameter, an object holding the
+    /// arguments from the given index on.
+    CreateRestParameter(usize),
     CallFunction {
         basic_block_id: BasicBlockId,
+        arguments: CallArguments,
+        context_depth: usize,
+    },
+    CallValue {
+        arguments: CallArguments,
+    },
+    /// Calls a system function with the arguments past those passed in
+    /// registers stored in the frame from `stack_offset` on.
+    CallSystemFunction {
+        function: SystemFunction,
         argument_count: usize,
+        stack_offset: usize,
     },
-    CallSystemFunction(SystemFunction),
     ReturnValue,
     Return,
     GoToBlock(BasicBlockId),
     DiscardValue,
+    Duplicate,
+    /// Copies the two values on top of the stack, like an object and the
+    /// key of a property that's read and then written.
+    Duplicate2,
+    /// Copies the value on top of the stack to below the given number of
+    /// values under it.
+    DuplicateUnder(usize),
 }
@@ -0,0 +1,432 @@
+use std::collections::HashSet;
+
+use almond::ast::{BinaryOperator, Function, Node, NodeKind, VariableDeclarationKind};
+use anyhow::{bail, Result};
+
+use super::{
+    captures::{assigned_names, nested_function_references, refers_to},
+    nodes::{
+        expression::evaluate_expression,
+        function_decl::{compile_function, declare_function, param_name},
+    },
+    BasicBlock, FlowGraph, FlowInstruction, Id, Scope, TailInstruction, Value,
+};
+
+/// Declares the `var` bindings and function declarations of a function or
+/// program body before any of its statements are compiled, so that they can
+/// be used ahead of the statements declaring them. Variables start out as
+/// undefined, and functions are declared before any of their bodies are
+/// compiled so that they can call each other. The body's `let` and `const`
+/// bindings are declared as well, but can't be read before their
+/// declarations run. Default values of the function's `params` are given
+/// to those without an argument before the functions are declared.
+pub(crate) fn hoist_declarations<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    block: &mut BasicBlock<'a>,
+    params: &[Node<'a>],
+    nodes: &[Node<'a>],
+) -> Result<()> {
+    let mut names = Vec::new();
+    for node in nodes {
+        collect_var_names(node, &mut names);
+    }
+
+    let lexical_names = lexical_declarations(nodes);
+    let other_names = names
+        .iter()
+        .cloned()
+        .chain(function_names(nodes))
+        .collect::<Vec<_>>();
+    check_lexical_declarations(&block.scope.borrow(), &lexical_names, &other_names)?;
+
+    // variables that nested functions refer to have to outlive the frame, so
+    // they move to an environment record that the functions get a link to.
+    // Parameters are copied as well, so that whether there was an argument
+    // for them is only checked once
+    let mut references = nested_function_references(nodes);
+    references.extend(nested_function_references(params));
+    let mut bindings = Vec::new();
+    let mut seen = HashSet::new();
+    let parameters = block.scope.borrow().parameter_names();
+
+    // a function that refers to `arguments` gets an object holding them,
+    // unless it has a parameter or a `let` or `const` of that name
+    let arguments = Id::new("arguments");
+    let arguments = (block.scope.borrow().has_own_arguments()
+        && refers_to(nodes, &arguments)
+        && !parameters.contains(&arguments)
+        && !lexical_names.iter().any(|(name, _)| *name == arguments))
+    .then_some(arguments);
+
+    // a function that is assigned to somewhere needs a variable as well
+    let assigned = assigned_names(nodes);
+    let assigned_functions = function_names(nodes)
+        .into_iter()
+        .filter(|name| assigned.contains(name));
+
+    for name in parameters
+        .into_iter()
+        .chain(arguments.clone())
+        .chain(names)
+        .chain(assigned_functions)
+    {
+        if !seen.insert(name.clone()) {
+            continue;
+        }
+
+        match block.scope.borrow().local_binding(&name) {
+            Some(Value::FunctionParameter(index)) => match params.get(index).map(|p| &p.kind) {
+                Some(NodeKind::RestElement { .. }) => {
+                    bindings.push((name, FlowInstruction::CreateRestParameter(index)))
+                }
+                _ => bindings.push((name, FlowInstruction::PushFunctionParameter(index))),
+            },
+            // a var named after a parameter or a system function keeps that binding
+            Some(_) => {}
+            None if Some(&name) == arguments.as_ref() => {
+                bindings.push((name, FlowInstruction::CreateArgumentsObject))
+            }
+            None => bindings.push((name, FlowInstruction::PushLiteralUndefined)),
+        }
+    }
+
+    for (name, _) in &lexical_names {
+        bindings.push((name.clone(), FlowInstruction::PushUninitialized));
+    }
+
+    bind_variables(block, bindings, &references);
+
+    for (name, is_const) in lexical_names {
+        block.scope.borrow_mut().declare_lexical(name, is_const);
+    }
+
+    for param in params {
+        if let NodeKind::AssignmentPattern { left, right } = &param.kind {
+            assign_default(flow_graph, block, left, right)?;
+        }
+    }
+
+    declare_functions(flow_graph, block, nodes, &assigned)
+}
+
+/// Gives the parameter `param` the value of `default` if it's undefined,
+/// which it is without an argument for it.
+fn assign_default<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    block: &mut BasicBlock<'a>,
+    param: &Node<'a>,
+    default: &Node<'a>,
+) -> Result<()> {
+    let name = param_name(param);
+
+    block.push(variable(block, &name));
+    block.push(FlowInstruction::PushLiteralUndefined);
+    block.push(FlowInstruction::ApplyBinaryOperator(
+        BinaryOperator::TripleEquals,
+    ));
+
+    let mut default_block = flow_graph.new_basic_block(default, block.scope.clone());
+    let continuation_block = flow_graph.new_basic_block(default, block.scope.clone());
+    block.tails.push(TailInstruction::ConditionalJump {
+        then_block: default_block.id,
+        else_block: continuation_block.id,
+    });
+
+    evaluate_expression(flow_graph, &mut default_block, default)?;
+    default_block.push(variable(&default_block, &name));
+    default_block.push(FlowInstruction::Assign);
+    default_block.push(FlowInstruction::DiscardValue);
+    default_block
+        .tails
+        .push(TailInstruction::Jump(continuation_block.id));
+    flow_graph.insert_block(default_block);
+
+    flow_graph.switch_to_block(block, continuation_block);
+
+    Ok(())
+}
+
+/// The instruction pushing the variable that `name` is bound to in the
+/// scope of `block` itself.
+fn variable(block: &BasicBlock, name: &Id) -> FlowInstruction {
+    match block.scope.borrow().local_binding(name) {
+        Some(Value::StackVariable { offset }) => FlowInstruction::PushStackVariable(offset),
+        Some(Value::CapturedVariable { slot }) => {
+            FlowInstruction::PushCapturedVariable { depth: 0, slot }
+        }
+        other => unreachable!("variable {:?}", other),
+    }
+}
+
+/// Declares the functions among `nodes` in the scope of `block`, before any
+/// of their bodies are compiled so that they can call each other. A function
+/// whose name is in `assigned` has a variable bound already, which is given
+/// the function object. Otherwise the name refers to the function itself,
+/// and calls to it are direct.
+fn declare_functions<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    block: &mut BasicBlock<'a>,
+    nodes: &[Node<'a>],
+    assigned: &HashSet<Id>,
+) -> Result<()> {
+    let functions = nodes
+        .iter()
+        .filter_map(|node| match &node.kind {
+            NodeKind::FunctionDeclaration { function } => Some(function),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut bodies = Vec::new();
+    for function in &functions {
+        let body = match function_name(function) {
+            Some(name) if assigned.contains(&name) => {
+                let body = flow_graph.reserve_basic_block_id();
+                block.push(FlowInstruction::PushFunction {
+                    basic_block_id: body,
+                    context_depth: 0,
+                });
+                let variable = variable(block, &name);
+                block.push(variable);
+                block.push(FlowInstruction::Assign);
+                block.push(FlowInstruction::DiscardValue);
+                body
+            }
+            _ => declare_function(flow_graph, &block.scope, function),
+        };
+        bodies.push(body);
+    }
+
+    for (function, body) in functions.into_iter().zip(bodies) {
+        compile_function(flow_graph, &block.scope, function, body)?;
+    }
+
+    Ok(())
+}
+
+/// Declares the `let` and `const` bindings and the functions of the
+/// statements `nodes` in the scope of `block`, which is a scope of their
+/// own, before the statements are compiled. Each time the statements run,
+/// their bindings are new and uninitialized, so the ones that functions
+/// nested in `scope_nodes` refer to get an environment record of their own
+/// rather than the function's. The functions can be called ahead of their
+/// declarations, like those of a function body.
+pub(crate) fn declare_lexical_bindings<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    block: &mut BasicBlock<'a>,
+    nodes: &[Node<'a>],
+    scope_nodes: &[Node<'a>],
+) -> Result<()> {
+    let mut names = function_names(nodes);
+    for node in nodes {
+        collect_var_names(node, &mut names);
+    }
+
+    let lexical_names = lexical_declarations(nodes);
+    check_lexical_declarations(&block.scope.borrow(), &lexical_names, &names)?;
+
+    // as in a function body, a function that is assigned to needs a variable
+    let assigned = assigned_names(nodes);
+    let mut bindings = function_names(nodes)
+        .into_iter()
+        .filter(|name| assigned.contains(name))
+        .map(|name| (name, FlowInstruction::PushLiteralUndefined))
+        .collect::<Vec<_>>();
+    bindings.extend(
+        lexical_names
+            .iter()
+            .map(|(name, _)| (name.clone(), FlowInstruction::PushUninitialized)),
+    );
+    bind_variables(block, bindings, &nested_function_references(scope_nodes));
+
+    for (name, is_const) in lexical_names {
+        block.scope.borrow_mut().declare_lexical(name, is_const);
+    }
+
+    declare_functions(flow_graph, block, nodes, &assigned)
+}
+
+/// Binds each name in the scope of `block`, and emits the code giving it its
+/// initial value. Names in `references` go into a new environment record,
+/// and the others into the frame.
+fn bind_variables(
+    block: &mut BasicBlock,
+    bindings: Vec<(Id, FlowInstruction)>,
+    references: &HashSet<Id>,
+) {
+    let mut captured = Vec::new();
+
+    for (name, initial_value) in bindings {
+        if references.contains(&name) {
+            captured.push((name, initial_value));
+            continue;
+        }
+
+        let offset = {
+            let mut scope = block.scope.borrow_mut();
+            let offset = scope.allocate_stack(8);
+            scope.insert(name, Value::StackVariable { offset });
+            offset
+        };
+
+        block.push(initial_value);
+        block.push(FlowInstruction::PushStackVariable(offset));
+        block.push(FlowInstruction::Assign);
+        block.push(FlowInstruction::DiscardValue);
+    }
+
+    if !captured.is_empty() {
+        block.push(FlowInstruction::CreateEnvironmentRecord(captured.len()));
+    }
+
+    for (name, initial_value) in captured {
+        let slot = {
+            let mut scope = block.scope.borrow_mut();
+            let slot = scope.allocate_environment_slot();
+            scope.insert(name, Value::CapturedVariable { slot });
+            slot
+        };
+
+        block.push(initial_value);
+        block.push(FlowInstruction::PushCapturedVariable { depth: 0, slot });
+        block.push(FlowInstruction::Assign);
+        block.push(FlowInstruction::DiscardValue);
+    }
+}
+
+/// Whether any of the statements `nodes` is a `let` or `const` declaration.
+pub(crate) fn has_lexical_declarations(nodes: &[Node]) -> bool {
+    !lexical_declarations(nodes).is_empty()
+}
+
+/// Whether any of the statements `nodes` of a block declares a binding that
+/// is scoped to the block: a `let`, a `const` or a function.
+pub(crate) fn has_block_declarations(nodes: &[Node]) -> bool {
+    has_lexical_declarations(nodes)
+        || nodes
+            .iter()
+            .any(|node| matches!(node.kind, NodeKind::FunctionDeclaration { .. }))
+}
+
+/// The names declared by the `let` and `const` statements among `nodes`,
+/// and whether each is a `const`.
+fn lexical_declarations(nodes: &[Node]) -> Vec<(Id, bool)> {
+    let mut names = Vec::new();
+
+    for node in nodes {
+        if let NodeKind::VariableDeclaration { declarations, kind } = &node.kind {
+            let is_const = match kind {
+                VariableDeclarationKind::Var => continue,
+                VariableDeclarationKind::Let => false,
+                VariableDeclarationKind::Const => true,
+            };
+
+            names.extend(
+                declared_names(declarations)
+                    .into_iter()
+                    .map(|name| (name, is_const)),
+            );
+        }
+    }
+
+    names
+}
+
+/// The names of the functions declared among `nodes`.
+fn function_names(nodes: &[Node]) -> Vec<Id> {
+    nodes
+        .iter()
+        .filter_map(|node| match &node.kind {
+            NodeKind::FunctionDeclaration { function } => function_name(function),
+            _ => None,
+        })
+        .collect()
+}
+
+fn function_name(function: &Function) -> Option<Id> {
+    match function.id.as_ref().as_ref().map(|id| &id.kind) {
+        Some(NodeKind::Identifier { name }) => Some(Id::new(name)),
+        _ => None,
+    }
+}
+
+fn declared_names(declarations: &[Node]) -> Vec<Id> {
+    declarations
+        .iter()
+        .filter_map(|declaration| match &declaration.kind {
+            NodeKind::VariableDeclarator { id, init: _ } => match &id.kind {
+                NodeKind::Identifier { name } => Some(Id::new(name)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Rejects `let` and `const` declarations of a name that is declared more
+/// than once in the same scope, as one of `other_names` or already bound
+/// in `scope`, like a parameter.
+fn check_lexical_declarations(
+    scope: &Scope,
+    lexical_names: &[(Id, bool)],
+    other_names: &[Id],
+) -> Result<()> {
+    let mut seen = HashSet::new();
+
+    for (name, _) in lexical_names {
+        if !seen.insert(name) || other_names.contains(name) || scope.has_binding(name) {
+            bail!(
+                "SyntaxError: Identifier '{}' has already been declared",
+                name.0
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects the names declared by `var` statements in `node`, without
+/// descending into nested functions, which have their own bindings.
+fn collect_var_names(node: &Node, names: &mut Vec<Id>) {
+    match &node.kind {
+        NodeKind::VariableDeclaration {
+            declarations,
+            kind: VariableDeclarationKind::Var,
+        } => names.extend(declared_names(declarations)),
+
+        NodeKind::BlockStatement { body } => {
+            for node in body {
+                collect_var_names(node, names);
+            }
+        }
+
+        NodeKind::IfStatement {
+            test: _,
+            consequent,
+            alternate,
+        } => {
+            collect_var_names(consequent, names);
+            if let Some(alternate) = &**alternate {
+                collect_var_names(alternate, names);
+            }
+        }
+
+        NodeKind::ForStatement {
+            init,
+            test: _,
+            update: _,
+            body,
+        } => {
+            if let Some(init) = &**init {
+                collect_var_names(init, names);
+            }
+            collect_var_names(body, names);
+        }
+
+        NodeKind::WhileStatement { test: _, body }
+        | NodeKind::DoWhileStatement { body, test: _ }
+        | NodeKind::LabeledStatement { label: _, body } => collect_var_names(body, names),
+
+        _ => {}
+    }
+}
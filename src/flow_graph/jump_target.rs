@@ -0,0 +1,20 @@
+use std::{cell::RefCell, rc::Rc};
+
+use super::{
+    basic_block::BasicBlockId,
+    scope::{Id, Scope},
+};
+
+/// The blocks that `break` and `continue` statements jump to from inside a
+/// statement that is being compiled. Loops have both; a labeled statement
+/// that isn't a loop can only be broken out of.
+#[derive(Debug)]
+pub struct JumpTarget {
+    pub labels: Vec<Id>,
+    pub break_block: BasicBlockId,
+    pub continue_block: Option<BasicBlockId>,
+
+    /// The scope that both blocks continue in. Jumping to them leaves the
+    /// scopes of the blocks nested in it.
+    pub scope: Rc<RefCell<Scope>>,
+}
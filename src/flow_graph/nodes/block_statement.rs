@@ -0,0 +1,48 @@
+use std::{cell::RefCell, rc::Rc};
+
+use almond::ast::{Node, NodeKind};
+use anyhow::Result;
+
+use crate::flow_graph::{
+    hoisting::{declare_lexical_bindings, has_block_declarations},
+    BasicBlock, FlowGraph, FlowInstruction, Scope,
+};
+
+/// Compiles the statements of a block. A block declaring `let` or `const`
+/// bindings or functions gets a scope of its own for them, which is left
+/// again at the end of the block. Its functions are declared before its
+/// statements, so they can be called ahead of their declarations.
+pub fn handle_block_statement<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    block: &mut BasicBlock<'a>,
+    body: &[Node<'a>],
+) -> Result<()> {
+    if !has_block_declarations(body) {
+        for node in body {
+            flow_graph.add_node_to_block(block, node)?;
+        }
+
+        return Ok(());
+    }
+
+    let outer_scope = block.scope.clone();
+    block.scope = Rc::new(RefCell::new(Scope::new(Some(outer_scope.clone()))));
+    declare_lexical_bindings(flow_graph, block, body, body)?;
+
+    for node in body {
+        if matches!(node.kind, NodeKind::FunctionDeclaration { .. }) {
+            continue;
+        }
+
+        flow_graph.add_node_to_block(block, node)?;
+    }
+
+    // the statements may have moved compilation on to a later block, which
+    // is in the scope of this one as well
+    if block.scope.borrow().has_environment_record() {
+        block.push(FlowInstruction::PopEnvironmentRecord);
+    }
+    block.scope = outer_scope;
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use almond::ast::Node;
+use anyhow::Result;
+
+use crate::flow_graph::{
+    nodes::expression::evaluate_expression, tail_instruction::TailInstruction, BasicBlock,
+    FlowGraph,
+};
+
+/// Lowers `test ? consequent : alternate` into a block for each arm, which
+/// both leave their value on the stack and jump to a continuation block in
+/// which compilation of the parent continues. The continuation is compiled
+/// separately for each arm whose value has a different type.
+pub fn handle_conditional_expression<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_node: &Node<'a>,
+    block: &mut BasicBlock<'a>,
+    test: &Node<'a>,
+    consequent: &Node<'a>,
+    alternate: &Node<'a>,
+) -> Result<()> {
+    evaluate_expression(flow_graph, block, test)?;
+
+    let consequent_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    let alternate_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    let continuation_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+
+    block.tails.push(TailInstruction::ConditionalJump {
+        then_block: consequent_block.id,
+        else_block: alternate_block.id,
+    });
+
+    for (mut arm_block, arm) in [(consequent_block, consequent), (alternate_block, alternate)] {
+        evaluate_expression(flow_graph, &mut arm_block, arm)?;
+        arm_block
+            .tails
+            .push(TailInstruction::Jump(continuation_block.id));
+        flow_graph.insert_block(arm_block);
+    }
+
+    flow_graph.switch_to_block(block, continuation_block);
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use almond::ast::{Node, NodeKind};
+use anyhow::Result;
+
+use crate::flow_graph::{
+    scope::{Id, Scope},
+    tail_instruction::TailInstruction,
+    BasicBlock, FlowGraph, FlowInstruction,
+};
+
+pub fn handle_continue_statement<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_node: &Node<'a>,
+    block: &mut BasicBlock<'a>,
+    label: &Option<Node<'a>>,
+) -> Result<()> {
+    let label = label.as_ref().map(|label| match &label.kind {
+        NodeKind::Identifier { name } => Id::new(name),
+        kind => unimplemented!("label {:?}", kind),
+    });
+
+    // the blocks left on the way out drop their environment records
+    let (target, scope) = flow_graph.continue_target(label.as_ref())?;
+    for _ in 0..Scope::environment_records_between(&block.scope, &scope) {
+        block.push(FlowInstruction::PopEnvironmentRecord);
+    }
+    block.tails.push(TailInstruction::Jump(target));
+
+    // whatever follows the continue in the same statement list is unreachable
+    let unreachable_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    flow_graph.switch_to_block(block, unreachable_block);
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use almond::ast::Node;
+use anyhow::Result;
+
+use crate::flow_graph::{
+    nodes::expression::evaluate_expression, tail_instruction::TailInstruction, BasicBlock,
+    FlowGraph,
+};
+
+/// Lowers a `do ... while` statement into a body block, a test block that
+/// branches back to the body, and an exit block in which compilation of the
+/// parent continues. Unlike `while`, the body is entered unconditionally,
+/// and `continue` jumps to the test block.
+pub fn handle_do_while_statement<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_node: &Node<'a>,
+    block: &mut BasicBlock<'a>,
+    body: &Node<'a>,
+    test: &Node<'a>,
+) -> Result<()> {
+    let mut body_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    let mut test_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    let exit_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+
+    let body_block_id = body_block.id;
+    flow_graph.push_jump_target(block.scope.clone(), exit_block.id, Some(test_block.id));
+    flow_graph.add_node_to_block(&mut body_block, body)?;
+    flow_graph.pop_jump_target();
+
+    body_block.tails.push(TailInstruction::Jump(test_block.id));
+
+    evaluate_expression(flow_graph, &mut test_block, test)?;
+    test_block.tails.push(TailInstruction::ConditionalJump {
+        then_block: body_block_id,
+        else_block: exit_block.id,
+    });
+
+    block.tails.push(TailInstruction::Jump(body_block_id));
+
+    flow_graph.insert_block(body_block);
+    flow_graph.insert_block(test_block);
+    flow_graph.switch_to_block(block, exit_block);
+
+    Ok(())
+}
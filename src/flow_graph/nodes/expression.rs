@@ -1,66 +1,90 @@
 use almond::ast::{
-    AssignmentOperator, BinaryOperator, LiteralValue, Node,
+    AssignmentOperator, BinaryOperator, LiteralValue, LogicalOperator, Node,
     NodeKind::{self, BinaryExpression, Identifier},
+    PropertyKind, UnaryOperator, UpdateOperator,
 };
+use anyhow::{bail, Result};
 
-use crate::flow_graph::{
-    basic_block::BasicBlock, flow_instruction::FlowInstruction, scope::Id, value::Value,
+use crate::{
+    codegen::REGISTER_ARGUMENT_COUNT,
+    flow_graph::{
+        basic_block::BasicBlock,
+        flow_instruction::{CallArguments, FlowInstruction},
+        nodes::{
+            conditional_expression::handle_conditional_expression,
+            function_decl::{handle_arrow_function_expression, handle_function_expression},
+            logical_expression::{handle_logical_expression, push_short_circuit},
+        },
+        scope::Id,
+        tail_instruction::TailInstruction,
+        value::Value,
+        FlowGraph,
+    },
+    number::number_to_string,
 };
 
-pub fn evaluate_expression(parent_block: &mut BasicBlock, node: &Node) {
+pub fn evaluate_expression<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_block: &mut BasicBlock<'a>,
+    node: &Node<'a>,
+) -> Result<()> {
     match &node.kind {
         NodeKind::CallExpression { arguments, callee } => {
+            // calls to declared functions are resolved statically, and
+            // anything else is called through the function object it
+            // evaluates to
             let callee_id = match &callee.kind {
-                NodeKind::Identifier { name } => Id::new(name),
-                kind => todo!("callee {:?}", kind),
+                NodeKind::Identifier { name } => Some(Id::new(name)),
+                _ => None,
             };
 
-            let callee = parent_block.scope.borrow().lookup(&callee_id);
-            if let Some(callee) = callee {
-                match callee {
+            let static_callee = callee_id
+                .as_ref()
+                .and_then(|id| parent_block.scope.borrow().lookup(id))
+                .filter(|value| matches!(value, Value::Function { .. } | Value::SystemFunction(_)));
+
+            if let (Some(callee_id), Some(static_callee)) = (callee_id, static_callee) {
+                match static_callee {
+                    // the callee takes missing arguments to be undefined
                     Value::Function {
                         id: _,
                         body,
-                        params,
+                        params: _,
                     } => {
-                        if params.len() != arguments.len() {
-                            todo!(
-                                "arity mismatch: {} params vs {} arguments",
-                                params.len(),
-                                arguments.len()
-                            );
-                        }
-
-                        for argument in arguments {
-                            evaluate_expression(parent_block, argument);
-                        }
+                        let arguments = evaluate_arguments(flow_graph, parent_block, arguments)?;
+
+                        let context_depth =
+                            parent_block.scope.borrow().environment_depth(&callee_id);
 
                         parent_block.push(FlowInstruction::CallFunction {
                             basic_block_id: body,
-                            argument_count: arguments.len(),
+                            arguments,
+                            context_depth,
                         });
                     }
 
                     Value::SystemFunction(function) => {
-                        if function.arity() != arguments.len() {
-                            todo!(
-                                "arity mismatch: {} params vs {} arguments",
-                                function.arity(),
-                                arguments.len()
-                            );
-                        }
-
-                        for argument in arguments {
-                            evaluate_expression(parent_block, argument);
-                        }
-
-                        parent_block.push(FlowInstruction::CallSystemFunction(function));
+                        let argument_count = function.arity().unwrap_or(arguments.len());
+                        let stack_offset = evaluate_system_arguments(
+                            flow_graph,
+                            parent_block,
+                            arguments,
+                            argument_count,
+                        )?;
+
+                        parent_block.push(FlowInstruction::CallSystemFunction {
+                            function,
+                            argument_count,
+                            stack_offset,
+                        });
                     }
 
-                    other => todo!("call expression to callee {:?}", other),
+                    other => unreachable!("static callee {:?}", other),
                 }
             } else {
-                todo!("undefined callee {:?}", callee_id);
+                evaluate_expression(flow_graph, parent_block, callee)?;
+                let arguments = evaluate_arguments(flow_graph, parent_block, arguments)?;
+                parent_block.push(FlowInstruction::CallValue { arguments });
             }
         }
 
@@ -69,8 +93,8 @@ pub fn evaluate_expression(parent_block: &mut BasicBlock, node: &Node) {
             left,
             right,
         } => {
-            evaluate_expression(parent_block, left);
-            evaluate_expression(parent_block, right);
+            evaluate_expression(flow_graph, parent_block, left)?;
+            evaluate_expression(flow_graph, parent_block, right)?;
             parent_block.push(FlowInstruction::ApplyBinaryOperator(*operator))
         }
 
@@ -84,8 +108,29 @@ pub fn evaluate_expression(parent_block: &mut BasicBlock, node: &Node) {
                         FlowInstruction::PushFunctionParameter(index)
                     }
                     Value::StackVariable { offset } => FlowInstruction::PushStackVariable(offset),
-                    value => todo!("evaluate identifier value {:?}", value),
-                })
+                    Value::CapturedVariable { slot } => FlowInstruction::PushCapturedVariable {
+                        depth: parent_block.scope.borrow().environment_depth(&id),
+                        slot,
+                    },
+                    Value::Undefined => FlowInstruction::PushLiteralUndefined,
+                    Value::Function {
+                        id: _,
+                        params: _,
+                        body,
+                    } => FlowInstruction::PushFunction {
+                        basic_block_id: body,
+                        context_depth: parent_block.scope.borrow().environment_depth(&id),
+                    },
+                    Value::SystemFunction(_) => {
+                        bail!("SyntaxError: System function '{}' can only be called", name)
+                    }
+                });
+
+                // a `let` or `const` may be read before its declaration runs
+                let binding = parent_block.scope.borrow().lexical_binding(&id);
+                if binding.is_some_and(|binding| !binding.initialized) {
+                    parent_block.push(FlowInstruction::CheckInitialized(name.clone()));
+                }
             } else {
                 todo!("undefined identifier {:?}", id);
             }
@@ -110,21 +155,463 @@ pub fn evaluate_expression(parent_block: &mut BasicBlock, node: &Node) {
             operator,
             right,
         } => {
-            evaluate_expression(parent_block, right);
+            if let Some(operator) = logical_assignment_operator(operator) {
+                return evaluate_logical_assignment(
+                    flow_graph,
+                    node,
+                    parent_block,
+                    &operator,
+                    left,
+                    right,
+                );
+            }
+
+            let reference = evaluate_reference(flow_graph, parent_block, left)?;
+
+            match compound_assignment_operator(operator) {
+                Some(operator) => {
+                    read_reference(flow_graph, parent_block, &reference, left)?;
+                    evaluate_expression(flow_graph, parent_block, right)?;
+                    parent_block.push(FlowInstruction::ApplyBinaryOperator(operator));
+                }
+                None => evaluate_expression(flow_graph, parent_block, right)?,
+            }
+
+            write_reference(flow_graph, parent_block, &reference, left)?;
+        }
+
+        NodeKind::UnaryExpression {
+            operator,
+            prefix: _,
+            argument,
+        } => match operator {
+            // typeof is the one way to refer to an undeclared variable
+            UnaryOperator::Typeof if is_undeclared(parent_block, argument) => {
+                parent_block.push(FlowInstruction::PushLiteralString("undefined".to_string()))
+            }
+
+            UnaryOperator::Typeof if is_system_function(parent_block, argument) => {
+                parent_block.push(FlowInstruction::PushLiteralString("function".to_string()))
+            }
+
+            UnaryOperator::Void => {
+                evaluate_expression(flow_graph, parent_block, argument)?;
+                parent_block.push(FlowInstruction::DiscardValue);
+                parent_block.push(FlowInstruction::PushLiteralUndefined);
+            }
+
+            UnaryOperator::Delete => todo!("delete operator"),
+
+            operator => {
+                evaluate_expression(flow_graph, parent_block, argument)?;
+                parent_block.push(FlowInstruction::ApplyUnaryOperator(*operator));
+            }
+        },
+
+        NodeKind::UpdateExpression {
+            operator,
+            argument,
+            prefix,
+        } => {
+            let operator = match operator {
+                UpdateOperator::Increment => BinaryOperator::Plus,
+                UpdateOperator::Decrement => BinaryOperator::Minus,
+            };
+
+            // a postfix update keeps a copy of the old value, converted to a
+            // number, under the target, and drops the new value that the
+            // assignment leaves
+            let reference = evaluate_reference(flow_graph, parent_block, argument)?;
+            read_reference(flow_graph, parent_block, &reference, argument)?;
+            if !*prefix {
+                parent_block.push(FlowInstruction::ApplyUnaryOperator(UnaryOperator::Plus));
+                parent_block.push(FlowInstruction::DuplicateUnder(reference.stack_size()));
+            }
+
+            parent_block.push(FlowInstruction::PushLiteralNumber(1.0));
+            parent_block.push(FlowInstruction::ApplyBinaryOperator(operator));
+            write_reference(flow_graph, parent_block, &reference, argument)?;
+
+            if !*prefix {
+                parent_block.push(FlowInstruction::DiscardValue);
+            }
+        }
+
+        NodeKind::LogicalExpression {
+            operator,
+            left,
+            right,
+        } => handle_logical_expression(flow_graph, node, parent_block, operator, left, right)?,
+
+        NodeKind::ConditionalExpression {
+            test,
+            consequent,
+            alternate,
+        } => handle_conditional_expression(
+            flow_graph,
+            node,
+            parent_block,
+            test,
+            consequent,
+            alternate,
+        )?,
+
+        NodeKind::MemberExpression {
+            object,
+            property,
+            computed,
+        } => {
+            evaluate_expression(flow_graph, parent_block, object)?;
 
-            match operator {
-                AssignmentOperator::PlusEquals => {
-                    evaluate_expression(parent_block, left);
-                    parent_block.push(FlowInstruction::ApplyBinaryOperator(BinaryOperator::Plus));
+            match property_name(property, *computed) {
+                Some(name) => parent_block.push(FlowInstruction::GetProperty(name)),
+                None => {
+                    evaluate_expression(flow_graph, parent_block, property)?;
+                    parent_block.push(FlowInstruction::GetComputedProperty);
                 }
+            }
+        }
 
-                other => unimplemented!("assignment expression with operator {:?}", other),
+        NodeKind::ObjectExpression { properties } => {
+            let mut initializers = Vec::new();
+
+            for property in properties {
+                match &property.kind {
+                    NodeKind::Property {
+                        key,
+                        value,
+                        kind: PropertyKind::Init,
+                    } => {
+                        initializers.push((property_name(key, false).expect("property key"), value))
+                    }
+                    other => todo!("object literal property {:?}", other),
+                }
+            }
+
+            // the object is created with the shape it ends up with, and its
+            // properties are set one at a time, so only the object and one
+            // value are on the stack at once, however deeply literals nest;
+            // a name given twice keeps the last value
+            let names = initializers.iter().map(|(name, _)| name.clone()).collect();
+            parent_block.push(FlowInstruction::CreateObject(names));
+
+            for (name, value) in initializers {
+                evaluate_expression(flow_graph, parent_block, value)?;
+                parent_block.push(FlowInstruction::InitProperty(name));
             }
+        }
 
-            evaluate_expression(parent_block, left);
-            parent_block.push(FlowInstruction::Assign)
+        NodeKind::FunctionExpression { function } => {
+            handle_function_expression(flow_graph, parent_block, function)?
         }
 
+        NodeKind::ArrowFunctionExpression {
+            function,
+            expression: _,
+        } => handle_arrow_function_expression(flow_graph, parent_block, function)?,
+
         kind => todo!("expression node {:?}", kind),
     }
+
+    Ok(())
+}
+
+/// Evaluates the arguments of a call, and stores each in a frame slot
+/// reserved for the call as soon as it's evaluated. Arguments that are
+/// calls themselves then don't have to find room for theirs among the
+/// registers backing the stack.
+fn evaluate_arguments<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_block: &mut BasicBlock<'a>,
+    arguments: &[Node<'a>],
+) -> Result<CallArguments> {
+    let offset = parent_block
+        .scope
+        .borrow_mut()
+        .allocate_stack(8 * arguments.len());
+
+    for (index, argument) in arguments.iter().enumerate() {
+        evaluate_expression(flow_graph, parent_block, argument)?;
+        parent_block.push(FlowInstruction::StoreArgument(offset + 8 * index));
+    }
+
+    Ok(CallArguments {
+        count: arguments.len(),
+        offset,
+    })
+}
+
+/// Evaluates the arguments of a call to a system function, which takes
+/// `count` of them. Extra arguments are only evaluated for their side
+/// effects, and missing ones are undefined. The handler picks its code by
+/// the types of the ones passed in registers, so those stay on the stack,
+/// and the rest are stored in the frame as they're evaluated, from the
+/// offset this returns on.
+fn evaluate_system_arguments<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_block: &mut BasicBlock<'a>,
+    arguments: &[Node<'a>],
+    count: usize,
+) -> Result<usize> {
+    let stack_offset = parent_block
+        .scope
+        .borrow_mut()
+        .allocate_stack(8 * count.saturating_sub(REGISTER_ARGUMENT_COUNT));
+
+    for index in 0..arguments.len().max(count) {
+        match arguments.get(index) {
+            Some(argument) => evaluate_expression(flow_graph, parent_block, argument)?,
+            None => parent_block.push(FlowInstruction::PushLiteralUndefined),
+        }
+
+        if index >= count {
+            parent_block.push(FlowInstruction::DiscardValue);
+        } else if let Some(stack_index) = index.checked_sub(REGISTER_ARGUMENT_COUNT) {
+            parent_block.push(FlowInstruction::StoreArgument(
+                stack_offset + 8 * stack_index,
+            ));
+        }
+    }
+
+    Ok(stack_offset)
+}
+
+/// What an assignment stores into. A property's object and computed key
+/// are evaluated before the value, and stay on the stack below it until
+/// it's stored.
+enum Reference {
+    Variable,
+    Property(String),
+    ComputedProperty,
+}
+
+impl Reference {
+    /// The number of values the reference keeps on the stack.
+    fn stack_size(&self) -> usize {
+        match self {
+            Reference::Variable => 0,
+            Reference::Property(_) => 1,
+            Reference::ComputedProperty => 2,
+        }
+    }
+}
+
+/// Evaluates the parts of an assignment's target that come before the
+/// value, so that they're evaluated once even if the target is read as well.
+fn evaluate_reference<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_block: &mut BasicBlock<'a>,
+    target: &Node<'a>,
+) -> Result<Reference> {
+    match &target.kind {
+        NodeKind::MemberExpression {
+            object,
+            property,
+            computed,
+        } => {
+            evaluate_expression(flow_graph, parent_block, object)?;
+
+            match property_name(property, *computed) {
+                Some(name) => Ok(Reference::Property(name)),
+                None => {
+                    evaluate_expression(flow_graph, parent_block, property)?;
+                    Ok(Reference::ComputedProperty)
+                }
+            }
+        }
+
+        _ => Ok(Reference::Variable),
+    }
+}
+
+/// Pushes the current value of an assignment's target, leaving the object
+/// and key of a property where they are.
+fn read_reference<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_block: &mut BasicBlock<'a>,
+    reference: &Reference,
+    target: &Node<'a>,
+) -> Result<()> {
+    match reference {
+        Reference::Variable => evaluate_expression(flow_graph, parent_block, target)?,
+        Reference::Property(name) => {
+            parent_block.push(FlowInstruction::Duplicate);
+            parent_block.push(FlowInstruction::GetProperty(name.clone()));
+        }
+        Reference::ComputedProperty => {
+            parent_block.push(FlowInstruction::Duplicate2);
+            parent_block.push(FlowInstruction::GetComputedProperty);
+        }
+    }
+
+    Ok(())
+}
+
+/// Lowers `left &&= right`, `left ||= right` or `left ??= right` like the
+/// logical operators: `right` is only evaluated, and `left` only assigned,
+/// if the current value of `left` doesn't decide the result. Otherwise that
+/// value is the result, and replaces the reference on the stack.
+fn evaluate_logical_assignment<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_node: &Node<'a>,
+    parent_block: &mut BasicBlock<'a>,
+    operator: &LogicalOperator,
+    left: &Node<'a>,
+    right: &Node<'a>,
+) -> Result<()> {
+    let reference = evaluate_reference(flow_graph, parent_block, left)?;
+    read_reference(flow_graph, parent_block, &reference, left)?;
+
+    let scope = parent_block.scope.clone();
+    let mut assign_block = flow_graph.new_basic_block(parent_node, scope.clone());
+    let mut keep_block = flow_graph.new_basic_block(parent_node, scope.clone());
+    let continuation_block = flow_graph.new_basic_block(parent_node, scope);
+    push_short_circuit(parent_block, operator, assign_block.id, keep_block.id);
+
+    if reference.stack_size() > 0 {
+        keep_block.push(FlowInstruction::DuplicateUnder(reference.stack_size()));
+        for _ in 0..=reference.stack_size() {
+            keep_block.push(FlowInstruction::DiscardValue);
+        }
+    }
+    keep_block
+        .tails
+        .push(TailInstruction::Jump(continuation_block.id));
+    flow_graph.insert_block(keep_block);
+
+    assign_block.push(FlowInstruction::DiscardValue);
+    evaluate_expression(flow_graph, &mut assign_block, right)?;
+    write_reference(flow_graph, &mut assign_block, &reference, left)?;
+    assign_block
+        .tails
+        .push(TailInstruction::Jump(continuation_block.id));
+    flow_graph.insert_block(assign_block);
+
+    flow_graph.switch_to_block(parent_block, continuation_block);
+
+    Ok(())
+}
+
+/// Stores the value on top of the stack into an assignment's target, and
+/// leaves it there in place of the reference.
+fn write_reference<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_block: &mut BasicBlock<'a>,
+    reference: &Reference,
+    target: &Node<'a>,
+) -> Result<()> {
+    match reference {
+        // the name of a named function expression refers to the function
+        // inside it, and the global `undefined` can't be given another
+        // value, so assigning to either does nothing
+        Reference::Variable if is_read_only(parent_block, target) => {}
+        Reference::Variable => {
+            evaluate_expression(flow_graph, parent_block, target)?;
+            let assignment = variable_assignment(parent_block, target)?;
+            parent_block.push(assignment);
+        }
+        Reference::Property(name) => parent_block.push(FlowInstruction::SetProperty(name.clone())),
+        Reference::ComputedProperty => parent_block.push(FlowInstruction::SetComputedProperty),
+    }
+
+    Ok(())
+}
+
+/// The name of the property that a member expression or property key refers
+/// to, if it's known at compile time: `a.b`, `a["b"]` and `{ b: 1 }` all
+/// refer to `b`, and `a[1]` to `1`.
+fn property_name(property: &Node, computed: bool) -> Option<String> {
+    match &property.kind {
+        Identifier { name } if !computed => Some(name.clone()),
+        NodeKind::Literal {
+            value: LiteralValue::String(name),
+        } => Some(name.clone()),
+        NodeKind::Literal {
+            value: LiteralValue::Number(number),
+        } => Some(number_to_string(*number)),
+        _ => None,
+    }
+}
+
+fn is_undeclared(parent_block: &BasicBlock, node: &Node) -> bool {
+    match &node.kind {
+        Identifier { name } => parent_block.scope.borrow().lookup(&Id::new(name)).is_none(),
+        _ => false,
+    }
+}
+
+/// Whether `node` is a name that refers to a value rather than a variable:
+/// a function that's never assigned to, or the global `undefined`.
+fn is_read_only(parent_block: &BasicBlock, node: &Node) -> bool {
+    match &node.kind {
+        Identifier { name } => matches!(
+            parent_block.scope.borrow().lookup(&Id::new(name)),
+            Some(Value::Function { .. } | Value::Undefined)
+        ),
+        _ => false,
+    }
+}
+
+fn is_system_function(parent_block: &BasicBlock, node: &Node) -> bool {
+    match &node.kind {
+        Identifier { name } => matches!(
+            parent_block.scope.borrow().lookup(&Id::new(name)),
+            Some(Value::SystemFunction(_))
+        ),
+        _ => false,
+    }
+}
+
+/// The instruction that assigns to the variable `target`. Assigning to a
+/// `const` is a compile error where it's known to run after the declaration,
+/// and otherwise raises an error at runtime, once the check that the binding
+/// is initialized has passed.
+fn variable_assignment(parent_block: &BasicBlock, target: &Node) -> Result<FlowInstruction> {
+    let binding = match &target.kind {
+        Identifier { name } => parent_block.scope.borrow().lexical_binding(&Id::new(name)),
+        _ => None,
+    };
+
+    match binding {
+        Some(binding) if binding.is_const && binding.initialized => {
+            bail!("TypeError: Assignment to constant variable.")
+        }
+        Some(binding) if binding.is_const => Ok(FlowInstruction::AssignConstant),
+        _ => Ok(FlowInstruction::Assign),
+    }
+}
+
+/// The binary operator that a compound assignment like `+=` applies, or
+/// `None` for plain `=` and the logical assignments, which only assign
+/// conditionally.
+fn compound_assignment_operator(operator: &AssignmentOperator) -> Option<BinaryOperator> {
+    match operator {
+        AssignmentOperator::Equals => None,
+        AssignmentOperator::PlusEquals => Some(BinaryOperator::Plus),
+        AssignmentOperator::MinusEquals => Some(BinaryOperator::Minus),
+        AssignmentOperator::ExponentEquals => Some(BinaryOperator::Exponentiation),
+        AssignmentOperator::AsteriskEquals => Some(BinaryOperator::Asterisk),
+        AssignmentOperator::SlashEquals => Some(BinaryOperator::Slash),
+        AssignmentOperator::PercentEquals => Some(BinaryOperator::Percent),
+        AssignmentOperator::ZeroFillLeftShiftEquals => Some(BinaryOperator::ZeroFillLeftShift),
+        AssignmentOperator::SignedRightShiftEquals => Some(BinaryOperator::SignedRightShift),
+        AssignmentOperator::ZeroFillRightShiftEquals => Some(BinaryOperator::ZeroFillRightShift),
+        AssignmentOperator::BitwiseOrEquals => Some(BinaryOperator::BitwiseOr),
+        AssignmentOperator::BitwiseXorEquals => Some(BinaryOperator::BitwiseXor),
+        AssignmentOperator::BitwiseAndEquals => Some(BinaryOperator::BitwiseAnd),
+        AssignmentOperator::LogicalOrEquals
+        | AssignmentOperator::LogicalAndEquals
+        | AssignmentOperator::NullishCoalescingEquals => None,
+    }
+}
+
+/// The logical operator that a logical assignment like `||=` short-circuits
+/// on.
+fn logical_assignment_operator(operator: &AssignmentOperator) -> Option<LogicalOperator> {
+    match operator {
+        AssignmentOperator::LogicalOrEquals => Some(LogicalOperator::LogicalOr),
+        AssignmentOperator::LogicalAndEquals => Some(LogicalOperator::LogicalAnd),
+        AssignmentOperator::NullishCoalescingEquals => Some(LogicalOperator::NullishCoalescing),
+        _ => None,
+    }
 }
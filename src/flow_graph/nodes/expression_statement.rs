@@ -1,13 +1,20 @@
 use almond::ast::Node;
+use anyhow::Result;
 
 use crate::flow_graph::{
     basic_block::BasicBlock, flow_instruction::FlowInstruction,
-    nodes::expression::evaluate_expression,
+    nodes::expression::evaluate_expression, FlowGraph,
 };
 
-pub fn handle_expression_statement<'a>(parent_block: &mut BasicBlock, expression: &Node<'a>) {
-    evaluate_expression(parent_block, expression);
+pub fn handle_expression_statement<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_block: &mut BasicBlock<'a>,
+    expression: &Node<'a>,
+) -> Result<()> {
+    evaluate_expression(flow_graph, parent_block, expression)?;
     parent_block
         .instructions
         .push(FlowInstruction::DiscardValue);
+
+    Ok(())
 }
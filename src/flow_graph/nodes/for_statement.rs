@@ -1,45 +1,121 @@
 use std::{cell::RefCell, rc::Rc};
 
 use almond::ast::{Node, NodeKind};
+use anyhow::Result;
 
 use crate::{
     flow_graph::{
-        nodes::variable_declaration::handle_variable_declarations,
-        tail_instruction::TailInstruction, BasicBlock,
+        hoisting::{declare_lexical_bindings, has_lexical_declarations},
+        nodes::{
+            expression::evaluate_expression, variable_declaration::handle_variable_declarations,
+        },
+        tail_instruction::TailInstruction,
+        BasicBlock, FlowInstruction,
     },
     FlowGraph, Scope,
 };
 
+/// Lowers a `for` statement into four blocks:
+///
+/// * the header evaluates `test` and branches to the body or the exit block,
+/// * the body runs the loop statement,
+/// * the update block evaluates `update` and jumps back to the header, and
+/// * the exit block, in which compilation of the parent continues.
+///
+/// A missing `test` makes the header jump to the body unconditionally.
+/// `break` jumps to the exit block and `continue` to the update block.
+///
+/// `let` and `const` bindings declared by `init` are in a scope around the
+/// loop. If closures capture them, every iteration gets a copy of their
+/// environment record, so that each closure sees the values of its own
+/// iteration.
 pub fn handle_for_statement<'a>(
     flow_graph: &mut FlowGraph<'a>,
     parent_node: &Node<'a>,
-    block: &mut BasicBlock,
+    block: &mut BasicBlock<'a>,
     init: &Option<Node<'a>>,
-    _test: &Option<Node<'a>>,
-    _update: &Option<Node<'a>>,
+    test: &Option<Node<'a>>,
+    update: &Option<Node<'a>>,
     body: &Node<'a>,
-) {
+) -> Result<()> {
+    let outer_scope = block.scope.clone();
     if let Some(init) = init {
+        if has_lexical_declarations(std::slice::from_ref(init)) {
+            block.scope = Rc::new(RefCell::new(Scope::new(Some(outer_scope.clone()))));
+            declare_lexical_bindings(
+                flow_graph,
+                block,
+                std::slice::from_ref(init),
+                std::slice::from_ref(parent_node),
+            )?;
+        }
+
         match &init.kind {
-            NodeKind::VariableDeclaration { declarations, kind: _ } => {
-                handle_variable_declarations(block, &declarations)
+            NodeKind::VariableDeclaration { declarations, kind } => {
+                handle_variable_declarations(flow_graph, block, declarations, kind)?
+            }
+            _ => {
+                evaluate_expression(flow_graph, block, init)?;
+                block.push(FlowInstruction::DiscardValue);
             }
-            _ => unimplemented!("for-loop init node {:?}", init),
         }
     }
 
-    let body_block = match &body.kind {
-        NodeKind::BlockStatement { body } => flow_graph.create_basic_block(
-            parent_node,
-            Rc::new(RefCell::new(Scope::new(Some(block.scope.clone())))),
-            &body,
-            false,
-            
-        ),
-        other => unimplemented!("for loop body node {:?}", other),
+    let iteration_record_size = {
+        let scope = block.scope.borrow();
+        (!Rc::ptr_eq(&block.scope, &outer_scope) && scope.has_environment_record())
+            .then(|| scope.environment_size())
     };
 
-    block.tails.push(TailInstruction::Jump(body_block));
+    let body_scope = Rc::new(RefCell::new(Scope::new(Some(block.scope.clone()))));
+
+    let mut header_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    let mut body_block = flow_graph.new_basic_block(parent_node, body_scope);
+    let mut update_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    let mut exit_block = flow_graph.new_basic_block(parent_node, outer_scope);
+
+    if let Some(size) = iteration_record_size {
+        block.push(FlowInstruction::CopyEnvironmentRecord(size));
+        update_block.push(FlowInstruction::CopyEnvironmentRecord(size));
+        exit_block.push(FlowInstruction::PopEnvironmentRecord);
+    }
+
+    // evaluating the test may move on to a later block than the header
+    let header_block_id = header_block.id;
+    if let Some(test) = test {
+        evaluate_expression(flow_graph, &mut header_block, test)?;
+        header_block.tails.push(TailInstruction::ConditionalJump {
+            then_block: body_block.id,
+            else_block: exit_block.id,
+        });
+    } else {
+        header_block
+            .tails
+            .push(TailInstruction::Jump(body_block.id));
+    }
+
+    flow_graph.push_jump_target(block.scope.clone(), exit_block.id, Some(update_block.id));
+    flow_graph.add_node_to_block(&mut body_block, body)?;
+    flow_graph.pop_jump_target();
+
+    body_block
+        .tails
+        .push(TailInstruction::Jump(update_block.id));
+
+    if let Some(update) = update {
+        evaluate_expression(flow_graph, &mut update_block, update)?;
+        update_block.push(FlowInstruction::DiscardValue);
+    }
+    update_block
+        .tails
+        .push(TailInstruction::Jump(header_block_id));
+
+    block.tails.push(TailInstruction::Jump(header_block_id));
+
+    flow_graph.insert_block(header_block);
+    flow_graph.insert_block(body_block);
+    flow_graph.insert_block(update_block);
+    flow_graph.switch_to_block(block, exit_block);
 
-    // todo!("what do I do with for loop block {:?}?", body_block)
+    Ok(())
 }
@@ -1,9 +1,11 @@
 use std::{cell::RefCell, rc::Rc};
 
-use almond::ast::{Function, NodeKind};
+use almond::ast::{Function, Node, NodeKind};
+use anyhow::Result;
 
 use crate::flow_graph::{
-    basic_block::BasicBlock,
+    basic_block::{BasicBlock, BasicBlockId},
+    flow_instruction::FlowInstruction,
     scope::{Id, Scope},
     value::Value,
     FlowGraph,
@@ -13,49 +15,151 @@ pub fn handle_function_decl<'a>(
     flow_graph: &mut FlowGraph<'a>,
     parent_block: &mut BasicBlock,
     function_node: &Function<'a>,
-) {
-    if let NodeKind::BlockStatement { body } = &function_node.body.kind {
-        let id = function_node.id.as_ref();
-        let id = &id.as_ref().map(|id| match &id.kind {
-            NodeKind::Identifier { name } => Id::new(name),
-            kind => todo!("function identifier {:?}", kind),
-        });
-
-        let mut scope = Scope::new(Some(parent_block.scope.clone()));
-
-        let params = function_node
-            .params
-            .iter()
-            .map(|node| match &node.kind {
-                NodeKind::Identifier { name } => Id::new(name),
-                kind => todo!("function parameter {:?}", kind),
-            })
-            .collect::<Vec<Id>>();
-
-        for (index, param) in params.iter().enumerate() {
-            scope.insert(param.clone(), Value::FunctionParameter(index));
+) -> Result<()> {
+    let body = declare_function(flow_graph, &parent_block.scope, function_node);
+    compile_function(flow_graph, &parent_block.scope, function_node, body)
+}
+
+/// Binds the function's name in `scope` to a body block that is yet to be
+/// compiled by `compile_function`, so the function can be called before its
+/// body is compiled.
+pub fn declare_function(
+    flow_graph: &mut FlowGraph,
+    scope: &Rc<RefCell<Scope>>,
+    function_node: &Function,
+) -> BasicBlockId {
+    let body = flow_graph.reserve_basic_block_id();
+
+    if let Some(id) = function_id(function_node) {
+        scope
+            .borrow_mut()
+            .insert(id, function_value(function_node, body));
+    }
+
+    body
+}
+
+/// Compiles a function expression and pushes a function object for it. The
+/// name of a named function expression is only bound inside the function.
+pub fn handle_function_expression<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_block: &mut BasicBlock<'a>,
+    function_node: &Function<'a>,
+) -> Result<()> {
+    let body = flow_graph.reserve_basic_block_id();
+
+    let scope = match function_id(function_node) {
+        Some(id) => {
+            let mut scope = Scope::new(Some(parent_block.scope.clone()));
+            scope.insert(id, function_value(function_node, body));
+            Rc::new(RefCell::new(scope))
         }
+        None => parent_block.scope.clone(),
+    };
+
+    compile_function(flow_graph, &scope, function_node, body)?;
+
+    parent_block.push(FlowInstruction::PushFunction {
+        basic_block_id: body,
+        context_depth: 0,
+    });
 
-        let scope = Rc::new(RefCell::new(scope));
+    Ok(())
+}
+
+fn function_id(function_node: &Function) -> Option<Id> {
+    (*function_node.id).as_ref().map(|id| match &id.kind {
+        NodeKind::Identifier { name } => Id::new(name),
+        kind => todo!("function identifier {:?}", kind),
+    })
+}
+
+fn function_value(function_node: &Function, body: BasicBlockId) -> Value {
+    Value::Function {
+        id: function_id(function_node),
+        params: function_params(function_node),
+        body,
+    }
+}
 
-        let body = flow_graph.create_basic_block(
+/// Compiles the body of a function declared in `scope` into the block
+/// `body`.
+pub fn compile_function<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    scope: &Rc<RefCell<Scope>>,
+    function_node: &Function<'a>,
+    body: BasicBlockId,
+) -> Result<()> {
+    let scope = Scope::new_function_scope(scope.clone());
+    compile_function_body(flow_graph, scope, function_node, body)
+}
+
+/// Compiles an arrow function and pushes a function object for it.
+pub fn handle_arrow_function_expression<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_block: &mut BasicBlock<'a>,
+    function_node: &Function<'a>,
+) -> Result<()> {
+    let body = flow_graph.reserve_basic_block_id();
+
+    let scope = Scope::new_arrow_function_scope(parent_block.scope.clone());
+    compile_function_body(flow_graph, scope, function_node, body)?;
+
+    parent_block.push(FlowInstruction::PushFunction {
+        basic_block_id: body,
+        context_depth: 0,
+    });
+
+    Ok(())
+}
+
+/// Compiles the body of a function, whose parameters are bound in `scope`,
+/// into the block `body`. The concise body of an arrow function is an
+/// expression rather than a block.
+fn compile_function_body<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    mut scope: Scope,
+    function_node: &Function<'a>,
+    body: BasicBlockId,
+) -> Result<()> {
+    for (index, param) in function_params(function_node).into_iter().enumerate() {
+        scope.insert(param, Value::FunctionParameter(index));
+    }
+
+    let scope = Rc::new(RefCell::new(scope));
+    let params = &function_node.params;
+
+    match &function_node.body.kind {
+        NodeKind::BlockStatement { body: nodes } => flow_graph.create_basic_block_with_id(
+            body,
             &function_node.body,
             scope,
-            body,
-            true,
-            
-        );
-
-        let value = Value::Function {
-            id: id.clone(),
             params,
+            nodes,
+            true,
+        )?,
+        _ => flow_graph.create_concise_body_block_with_id(
             body,
-        };
+            &function_node.body,
+            scope,
+            params,
+        )?,
+    };
 
-        if let Some(id) = id {
-            parent_block.scope.borrow_mut().insert(id.clone(), value);
-        }
-    } else {
-        todo!("function body {:?}", &function_node.body.kind);
+    Ok(())
+}
+
+fn function_params(function_node: &Function) -> Vec<Id> {
+    function_node.params.iter().map(param_name).collect()
+}
+
+/// The name bound by a parameter, which can have a default value or be a
+/// rest parameter.
+pub(crate) fn param_name(param: &Node) -> Id {
+    match &param.kind {
+        NodeKind::Identifier { name } => Id::new(name),
+        NodeKind::AssignmentPattern { left, right: _ } => param_name(left),
+        NodeKind::RestElement { argument } => param_name(argument),
+        kind => unreachable!("function parameter {:?}", kind),
     }
 }
@@ -0,0 +1,52 @@
+use almond::ast::Node;
+use anyhow::Result;
+
+use crate::flow_graph::{
+    nodes::expression::evaluate_expression, tail_instruction::TailInstruction, BasicBlock,
+    FlowGraph,
+};
+
+/// Lowers an `if` statement into a then block, an optional else block and a
+/// continuation block in which both branches rejoin. An `else if` chain is
+/// simply an `if` statement compiled into the else block.
+pub fn handle_if_statement<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_node: &Node<'a>,
+    block: &mut BasicBlock<'a>,
+    test: &Node<'a>,
+    consequent: &Node<'a>,
+    alternate: &Option<Node<'a>>,
+) -> Result<()> {
+    evaluate_expression(flow_graph, block, test)?;
+
+    let mut then_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    let mut else_block = alternate
+        .as_ref()
+        .map(|_| flow_graph.new_basic_block(parent_node, block.scope.clone()));
+    let continuation_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+
+    block.tails.push(TailInstruction::ConditionalJump {
+        then_block: then_block.id,
+        else_block: else_block
+            .as_ref()
+            .map_or(continuation_block.id, |else_block| else_block.id),
+    });
+
+    flow_graph.add_node_to_block(&mut then_block, consequent)?;
+    then_block
+        .tails
+        .push(TailInstruction::Jump(continuation_block.id));
+    flow_graph.insert_block(then_block);
+
+    if let (Some(mut else_block), Some(alternate)) = (else_block.take(), alternate) {
+        flow_graph.add_node_to_block(&mut else_block, alternate)?;
+        else_block
+            .tails
+            .push(TailInstruction::Jump(continuation_block.id));
+        flow_graph.insert_block(else_block);
+    }
+
+    flow_graph.switch_to_block(block, continuation_block);
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use almond::ast::{Node, NodeKind};
+use anyhow::Result;
+
+use crate::flow_graph::{scope::Id, tail_instruction::TailInstruction, BasicBlock, FlowGraph};
+
+/// Compiles a labeled statement. A label on a loop (or on another labeled
+/// statement) is handed on to the loop, which registers it together with its
+/// own jump targets. Any other statement can only be broken out of, so it
+/// gets an exit block of its own for `break label` to jump to.
+pub fn handle_labeled_statement<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_node: &Node<'a>,
+    block: &mut BasicBlock<'a>,
+    label: &Node<'a>,
+    body: &Node<'a>,
+) -> Result<()> {
+    let label = match &label.kind {
+        NodeKind::Identifier { name } => Id::new(name),
+        kind => unimplemented!("label {:?}", kind),
+    };
+
+    flow_graph.push_pending_label(label);
+
+    match &body.kind {
+        NodeKind::ForStatement { .. }
+        | NodeKind::WhileStatement { .. }
+        | NodeKind::DoWhileStatement { .. }
+        | NodeKind::LabeledStatement { .. } => flow_graph.add_node_to_block(block, body),
+
+        _ => {
+            let exit_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+
+            flow_graph.push_jump_target(block.scope.clone(), exit_block.id, None);
+            flow_graph.add_node_to_block(block, body)?;
+            flow_graph.pop_jump_target();
+
+            block.tails.push(TailInstruction::Jump(exit_block.id));
+            flow_graph.switch_to_block(block, exit_block);
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use almond::ast::{BinaryOperator, LogicalOperator, Node};
+use anyhow::Result;
+
+use crate::flow_graph::{
+    nodes::expression::evaluate_expression, tail_instruction::TailInstruction, BasicBlock,
+    BasicBlockId, FlowGraph, FlowInstruction,
+};
+
+/// Lowers `left && right`, `left || right` or `left ?? right` so that `right`
+/// is only evaluated if `left` doesn't decide the result. A copy of `left` is
+/// tested, and either left on the stack as the result or replaced by `right`
+/// in a separate block. Both paths join in a continuation block, in which
+/// compilation of the parent continues.
+pub fn handle_logical_expression<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_node: &Node<'a>,
+    block: &mut BasicBlock<'a>,
+    operator: &LogicalOperator,
+    left: &Node<'a>,
+    right: &Node<'a>,
+) -> Result<()> {
+    evaluate_expression(flow_graph, block, left)?;
+
+    let mut right_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    let continuation_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    push_short_circuit(block, operator, right_block.id, continuation_block.id);
+
+    right_block.push(FlowInstruction::DiscardValue);
+    evaluate_expression(flow_graph, &mut right_block, right)?;
+    right_block
+        .tails
+        .push(TailInstruction::Jump(continuation_block.id));
+    flow_graph.insert_block(right_block);
+
+    flow_graph.switch_to_block(block, continuation_block);
+
+    Ok(())
+}
+
+/// Ends `block` with a jump on a copy of the value on top of the stack: to
+/// `right_block` if the value doesn't decide the result of `operator`, and
+/// to `decided_block` if it does. The value itself stays on the stack.
+pub(crate) fn push_short_circuit(
+    block: &mut BasicBlock,
+    operator: &LogicalOperator,
+    right_block: BasicBlockId,
+    decided_block: BasicBlockId,
+) {
+    block.push(FlowInstruction::Duplicate);
+
+    // `??` tests whether the copy is null or undefined, which are the only
+    // values loosely equal to null
+    if *operator == LogicalOperator::NullishCoalescing {
+        block.push(FlowInstruction::PushLiteralNull);
+        block.push(FlowInstruction::ApplyBinaryOperator(
+            BinaryOperator::EqualsEquals,
+        ));
+    }
+
+    block.tails.push(match operator {
+        LogicalOperator::LogicalAnd | LogicalOperator::NullishCoalescing => {
+            TailInstruction::ConditionalJump {
+                then_block: right_block,
+                else_block: decided_block,
+            }
+        }
+        LogicalOperator::LogicalOr => TailInstruction::ConditionalJump {
+            then_block: decided_block,
+            else_block: right_block,
+        },
+    });
+}
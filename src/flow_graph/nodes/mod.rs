@@ -1,6 +1,15 @@
+pub mod block_statement;
+pub mod break_statement;
+pub mod conditional_expression;
+pub mod continue_statement;
+pub mod do_while_statement;
 pub mod expression;
 pub mod expression_statement;
 pub mod for_statement;
 pub mod function_decl;
+pub mod if_statement;
+pub mod labeled_statement;
+pub mod logical_expression;
 pub mod return_statement;
 pub mod variable_declaration;
+pub mod while_statement;
@@ -1,14 +1,35 @@
 use almond::ast::Node;
+use anyhow::Result;
 
-use crate::flow_graph::{basic_block::BasicBlock, flow_instruction::FlowInstruction};
+use crate::flow_graph::{
+    basic_block::BasicBlock, flow_instruction::FlowInstruction, tail_instruction::TailInstruction,
+    FlowGraph,
+};
 
 use super::expression::evaluate_expression;
 
-pub fn handle_return_statement(block: &mut BasicBlock, argument: &Option<Node>) {
+/// Puts the return value in place and jumps to the function's epilogue.
+/// The statements following the return go into a block that nothing jumps
+/// to, so they are never compiled.
+pub fn handle_return_statement<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_node: &Node<'a>,
+    block: &mut BasicBlock<'a>,
+    argument: &Option<Node<'a>>,
+) -> Result<()> {
     if let Some(argument) = argument {
-        evaluate_expression(block, argument);
+        evaluate_expression(flow_graph, block, argument)?;
         block.push(FlowInstruction::ReturnValue);
     } else {
-        todo!("return with no argument");
+        block.push(FlowInstruction::Return);
     }
+
+    block
+        .tails
+        .push(TailInstruction::Jump(flow_graph.return_target()));
+
+    let unreachable_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    flow_graph.switch_to_block(block, unreachable_block);
+
+    Ok(())
 }
@@ -1,13 +1,19 @@
-use almond::ast::{Node, NodeKind};
+use almond::ast::{Node, NodeKind, VariableDeclarationKind};
+use anyhow::{bail, Result};
 
 use crate::{
     flow_graph::{
         nodes::expression::evaluate_expression, BasicBlock, FlowInstruction,
     },
-    Id, Value,
+    FlowGraph, Id, Value,
 };
 
-pub fn handle_variable_declarations<'a>(block: &mut BasicBlock, declarations: &Vec<Node<'a>>) {
+pub fn handle_variable_declarations<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    block: &mut BasicBlock<'a>,
+    declarations: &Vec<Node<'a>>,
+    kind: &VariableDeclarationKind,
+) -> Result<()> {
     for declaration in declarations {
         match &declaration.kind {
             NodeKind::VariableDeclarator { id: node, init } => {
@@ -16,27 +22,55 @@ pub fn handle_variable_declarations<'a>(block: &mut BasicBlock, declarations: &V
                     other => unimplemented!("identifier kind {:?}", other),
                 };
 
-                {
-                    let mut scope = block.scope.borrow_mut();
-                    let offset = scope.allocate_stack(8);
-                    scope.insert(id.clone(), Value::StackVariable { offset });
+                if *kind == VariableDeclarationKind::Var {
+                    // the binding was hoisted, and a declaration without an
+                    // initialiser leaves it alone
+                    if let Some(init) = init.as_ref() {
+                        evaluate_expression(flow_graph, block, init)?;
+
+                        // the global `undefined` can't be given another value
+                        if matches!(block.scope.borrow().lookup(&id), Some(Value::Undefined)) {
+                            block.push(FlowInstruction::DiscardValue);
+                            continue;
+                        }
+
+                        lookup_identifier(block, &id)?;
+                        block.instructions.push(FlowInstruction::Assign);
+                        block.instructions.push(FlowInstruction::DiscardValue);
+                    }
+
+                    continue;
+                }
+
+                // `let` and `const` are declared by the block they're
+                // directly in, so one anywhere else has no binding of its own
+                if !block.scope.borrow().has_lexical_binding(&id) {
+                    bail!("SyntaxError: Lexical declaration cannot appear in a single-statement context");
                 }
 
-                if let Some(init) = init.as_ref() {
-                    evaluate_expression(block, init);
-                } else {
-                    block.instructions.push(FlowInstruction::PushLiteralNull);
+                // the binding is only initialized once the initialiser has
+                // been evaluated, and `let` without one makes it undefined
+                match init.as_ref() {
+                    Some(init) => evaluate_expression(flow_graph, block, init)?,
+                    None if *kind == VariableDeclarationKind::Const => {
+                        bail!("SyntaxError: Missing initializer in const declaration")
+                    }
+                    None => block.push(FlowInstruction::PushLiteralUndefined),
                 }
 
-                lookup_identifier(block, &id);
-                block.instructions.push(FlowInstruction::Assign);
+                lookup_identifier(block, &id)?;
+                block.push(FlowInstruction::Assign);
+                block.push(FlowInstruction::DiscardValue);
+                block.scope.borrow_mut().initialize_lexical(&id);
             }
             _ => unimplemented!("variable declaration node {:?}", declaration),
         }
     }
+
+    Ok(())
 }
 
-fn lookup_identifier(block: &mut BasicBlock, id: &Id) {
+fn lookup_identifier(block: &mut BasicBlock, id: &Id) -> Result<()> {
     let value = block.scope.borrow().lookup(id);
 
     match value {
@@ -44,12 +78,24 @@ fn lookup_identifier(block: &mut BasicBlock, id: &Id) {
             Value::StackVariable { offset } => {
                 block.push(FlowInstruction::PushStackVariable(offset))
             }
-            Value::Function { id: _, params: _, body: _ } => todo!("lookup function"),
+            // a function that a declaration assigns to has a variable
+            Value::Function { id: _, params: _, body: _ } => unreachable!("lookup function"),
             Value::FunctionParameter(index) => {
                 block.push(FlowInstruction::PushFunctionParameter(index))
             }
-            Value::SystemFunction(function) => todo!("lookup system function {:?}", function),
+            Value::CapturedVariable { slot } => {
+                let depth = block.scope.borrow().environment_depth(id);
+                block.push(FlowInstruction::PushCapturedVariable { depth, slot })
+            }
+            Value::SystemFunction(_) => {
+                bail!("SyntaxError: System function '{}' can only be called", id.0)
+            }
+            // a `var` of the global `undefined` doesn't assign to it, and a
+            // `let` or `const` of it is already declared
+            Value::Undefined => unreachable!("lookup undefined"),
         },
         None => todo!("Identifier {:?} is not defined", id),
     }
+
+    Ok(())
 }
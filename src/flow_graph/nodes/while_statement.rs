@@ -0,0 +1,46 @@
+use almond::ast::Node;
+use anyhow::Result;
+
+use crate::flow_graph::{
+    nodes::expression::evaluate_expression, tail_instruction::TailInstruction, BasicBlock,
+    FlowGraph,
+};
+
+/// Lowers a `while` statement into a header block that evaluates `test`, a
+/// body block that jumps back to the header, and an exit block in which
+/// compilation of the parent continues.
+pub fn handle_while_statement<'a>(
+    flow_graph: &mut FlowGraph<'a>,
+    parent_node: &Node<'a>,
+    block: &mut BasicBlock<'a>,
+    test: &Node<'a>,
+    body: &Node<'a>,
+) -> Result<()> {
+    let mut header_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    let mut body_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+    let exit_block = flow_graph.new_basic_block(parent_node, block.scope.clone());
+
+    // evaluating the test may move on to a later block than the header
+    let header_block_id = header_block.id;
+    evaluate_expression(flow_graph, &mut header_block, test)?;
+    header_block.tails.push(TailInstruction::ConditionalJump {
+        then_block: body_block.id,
+        else_block: exit_block.id,
+    });
+
+    flow_graph.push_jump_target(block.scope.clone(), exit_block.id, Some(header_block_id));
+    flow_graph.add_node_to_block(&mut body_block, body)?;
+    flow_graph.pop_jump_target();
+
+    body_block
+        .tails
+        .push(TailInstruction::Jump(header_block_id));
+
+    block.tails.push(TailInstruction::Jump(header_block_id));
+
+    flow_graph.insert_block(header_block);
+    flow_graph.insert_block(body_block);
+    flow_graph.switch_to_block(block, exit_block);
+
+    Ok(())
+}
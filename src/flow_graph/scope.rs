@@ -5,39 +5,220 @@ use super::value::Value;
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Id(pub String);
 
+/// A `let` or `const` binding, which can't be used before its declaration
+/// has run, and which can't be assigned to if it's a `const`.
+#[derive(Clone, Copy, Debug)]
+pub struct LexicalBinding {
+    pub is_const: bool,
+
+    /// Set once the declaration has been compiled. Code of the same function
+    /// that is compiled after it runs after it as well, so it doesn't need
+    /// to check that the binding has a value.
+    pub initialized: bool,
+}
+
 #[derive(Default, Debug)]
 pub struct Scope {
     parent: Option<Rc<RefCell<Scope>>>,
     bindings: HashMap<Id, Value>,
-    stack_offset: usize,
-    pub stack_allocation: usize,
+
+    /// The bindings declared with `let` or `const`.
+    lexical_bindings: HashMap<Id, LexicalBinding>,
+
+    /// The size of the variables in the frame, if this scope owns it.
+    frame_size: usize,
+
+    /// Set on the scope of a function body, whose variables live in a frame
+    /// and environment record of their own.
+    is_function: bool,
+
+    /// Set on the scope of an arrow function body as well, which refers to
+    /// the `arguments` of the function around it rather than its own.
+    is_arrow_function: bool,
+
+    /// The number of captured variables, which live in an environment record
+    /// rather than the frame: the function's, or for a block, one of its own.
+    environment_size: usize,
 }
 
 impl Scope {
     pub fn new(parent: Option<Rc<RefCell<Scope>>>) -> Self {
-        let stack_offset = if let Some(ref parent) = parent {
-            let parent = parent.borrow();
-            parent.stack_offset + parent.stack_allocation
-        } else {
-            0
-        };
-
         Scope {
             parent,
-            bindings: HashMap::new(),
-            stack_offset,
-            stack_allocation: 0,
+            ..Scope::default()
         }
     }
 
+    /// Creates the scope of a function body. The function gets a stack frame
+    /// of its own, which the scopes nested in it allocate from as well.
+    pub fn new_function_scope(parent: Rc<RefCell<Scope>>) -> Self {
+        Scope {
+            parent: Some(parent),
+            is_function: true,
+            ..Scope::default()
+        }
+    }
+
+    /// Creates the scope of an arrow function body, which is a function
+    /// scope without an `arguments` of its own.
+    pub fn new_arrow_function_scope(parent: Rc<RefCell<Scope>>) -> Self {
+        Scope {
+            is_arrow_function: true,
+            ..Scope::new_function_scope(parent)
+        }
+    }
+
+    /// Whether this is the scope of a function body that has an `arguments`
+    /// of its own, which any function but an arrow function does.
+    pub fn has_own_arguments(&self) -> bool {
+        self.is_function && !self.is_arrow_function
+    }
+
     pub fn insert(&mut self, name: Id, value: Value) {
         self.bindings.insert(name, value);
     }
 
+    /// Reserves `size` bytes of the frame of the enclosing function,
+    /// returning the offset of the first. All the blocks of a function share
+    /// its frame, so nested scopes allocate from the function scope.
     pub fn allocate_stack(&mut self, size: usize) -> usize {
-        self.stack_offset += size;
-        self.stack_allocation += size;
-        self.stack_offset
+        match self.parent.as_ref() {
+            Some(parent) if !self.is_function => parent.borrow_mut().allocate_stack(size),
+            _ => {
+                let offset = self.frame_size;
+                self.frame_size += size;
+                offset
+            }
+        }
+    }
+
+    /// The size of the variables in the frame of the enclosing function.
+    pub fn frame_size(&self) -> usize {
+        match self.parent.as_ref() {
+            Some(parent) if !self.is_function => parent.borrow().frame_size(),
+            _ => self.frame_size,
+        }
+    }
+
+    /// Marks `name`, bound in this scope, as a `let` or `const` binding whose
+    /// declaration is yet to run.
+    pub fn declare_lexical(&mut self, name: Id, is_const: bool) {
+        self.lexical_bindings.insert(
+            name,
+            LexicalBinding {
+                is_const,
+                initialized: false,
+            },
+        );
+    }
+
+    /// Marks the `let` or `const` binding `name` of this scope as initialized
+    /// by its declaration.
+    pub fn initialize_lexical(&mut self, name: &Id) {
+        if let Some(binding) = self.lexical_bindings.get_mut(name) {
+            binding.initialized = true;
+        }
+    }
+
+    /// Whether `name` is a `let` or `const` binding of this scope itself.
+    pub fn has_lexical_binding(&self, name: &Id) -> bool {
+        self.lexical_bindings.contains_key(name)
+    }
+
+    /// The `let` or `const` binding that `name` refers to, if it is one. A
+    /// binding outside the innermost function never counts as initialized,
+    /// since the function could be called before its declaration runs.
+    pub fn lexical_binding(&self, name: &Id) -> Option<LexicalBinding> {
+        if self.bindings.contains_key(name) {
+            return self.lexical_bindings.get(name).copied();
+        }
+
+        let binding = self.parent.as_ref()?.borrow().lexical_binding(name)?;
+        Some(LexicalBinding {
+            initialized: binding.initialized && !self.is_function,
+            ..binding
+        })
+    }
+
+    /// The number of environment records that code in `scope` leaves behind
+    /// when it jumps out to `target`, which is `scope` or one enclosing it.
+    pub fn environment_records_between(
+        scope: &Rc<RefCell<Scope>>,
+        target: &Rc<RefCell<Scope>>,
+    ) -> usize {
+        let mut count = 0;
+        let mut scope = scope.clone();
+        while !Rc::ptr_eq(&scope, target) {
+            let parent = {
+                let inner = scope.borrow();
+                count += inner.has_environment_record() as usize;
+                inner.parent.clone().expect("jump out of the function")
+            };
+            scope = parent;
+        }
+
+        count
+    }
+
+    /// Reserves a slot in the environment record, returning its index.
+    pub fn allocate_environment_slot(&mut self) -> usize {
+        let slot = self.environment_size;
+        self.environment_size += 1;
+        slot
+    }
+
+    /// Whether variables of this scope live in an environment record of its
+    /// own.
+    pub fn has_environment_record(&self) -> bool {
+        self.environment_size > 0
+    }
+
+    /// The number of captured variables bound in this scope.
+    pub fn environment_size(&self) -> usize {
+        self.environment_size
+    }
+
+    /// Whether `name` is bound in this scope itself, rather than a parent.
+    pub fn has_binding(&self, name: &Id) -> bool {
+        self.bindings.contains_key(name)
+    }
+
+    /// The names of the function parameters bound in this scope, in order.
+    pub fn parameter_names(&self) -> Vec<Id> {
+        let mut parameters = self
+            .bindings
+            .iter()
+            .filter_map(|(name, value)| match value {
+                Value::FunctionParameter(index) => Some((*index, name.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        parameters.sort_by_key(|(index, _)| *index);
+        parameters.into_iter().map(|(_, name)| name).collect()
+    }
+
+    /// The binding of `name` in this scope itself, ignoring the parents.
+    pub fn local_binding(&self, name: &Id) -> Option<Value> {
+        self.bindings.get(name).cloned()
+    }
+
+    /// The number of links to follow from the innermost environment record
+    /// visible in this scope to reach the one visible where `name` is bound.
+    /// Functions without captured variables don't have a record of their
+    /// own, so only those that do count, as do blocks with captured `let` or
+    /// `const` bindings.
+    pub fn environment_depth(&self, name: &Id) -> usize {
+        if self.bindings.contains_key(name) {
+            return 0;
+        }
+
+        let depth = self.has_environment_record() as usize;
+
+        match self.parent.as_ref() {
+            Some(parent) => depth + parent.borrow().environment_depth(name),
+            None => depth,
+        }
     }
 
     pub fn lookup(&self, name: &Id) -> Option<Value> {
@@ -3,5 +3,11 @@ use super::BasicBlockId;
 #[derive(Debug, Clone)]
 pub enum TailInstruction {
     Jump(BasicBlockId),
-    ConditionalJump(BasicBlockId)
+
+    /// Pops a value off the stack and jumps to `then_block` if it is truthy,
+    /// or to `else_block` otherwise.
+    ConditionalJump {
+        then_block: BasicBlockId,
+        else_block: BasicBlockId,
+    },
 }
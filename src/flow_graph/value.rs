@@ -21,5 +21,15 @@ pub enum Value {
     },
 
     FunctionParameter(usize),
+
+    /// A variable that nested functions capture, held in slot `slot` of the
+    /// environment record of the function declaring it.
+    CapturedVariable {
+        slot: usize,
+    },
+
     SystemFunction(SystemFunction),
+
+    /// The global `undefined`, which can't be given another value.
+    Undefined,
 }
@@ -2,6 +2,9 @@ use std::{fmt::Debug, rc::Rc};
 
 use crate::codegen::CodegenStackEntry;
 
+/// A Rust function that compiled code calls for a system function. It takes
+/// and returns values in the encoding that compiled code uses, which
+/// `JsValue` decodes.
 pub type SystemFunctionHandlerFn = *const extern "win64" fn();
 pub type SystemFunctionGeneratorFn =
     Box<dyn Fn(&[CodegenStackEntry]) -> Option<SystemFunctionHandlerFn>>;
@@ -9,7 +12,7 @@ pub type SystemFunctionGeneratorFn =
 #[derive(Clone)]
 pub struct SystemFunction {
     name: String,
-    arity: usize,
+    arity: Option<usize>,
     generator: Rc<SystemFunctionGeneratorFn>,
 }
 
@@ -17,7 +20,17 @@ impl SystemFunction {
     pub fn new(name: String, arity: usize, generator: SystemFunctionGeneratorFn) -> Self {
         Self {
             name,
-            arity,
+            arity: Some(arity),
+            generator: Rc::new(generator),
+        }
+    }
+
+    /// Creates a system function that takes any number of arguments. The
+    /// generator gets all of them.
+    pub fn variadic(name: String, generator: SystemFunctionGeneratorFn) -> Self {
+        Self {
+            name,
+            arity: None,
             generator: Rc::new(generator),
         }
     }
@@ -25,10 +38,16 @@ impl SystemFunction {
     pub fn name(&self) -> &str {
         &self.name
     }
-    pub fn arity(&self) -> usize {
+
+    /// The number of arguments the function takes, or `None` if it's
+    /// variadic.
+    pub fn arity(&self) -> Option<usize> {
         self.arity
     }
 
+    /// The handler for a call with arguments of the given types. Arguments
+    /// past those passed in registers are stored in the frame as they're
+    /// evaluated, so they're always dynamic.
     pub fn handler_fn(&self, arguments: &[CodegenStackEntry]) -> Option<SystemFunctionHandlerFn> {
         (self.generator)(arguments)
     }
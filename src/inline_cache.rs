@@ -0,0 +1,124 @@
+//! Inline caches remember, for one property access in compiled code, which
+//! slot the property is in for the shapes of the objects the access has seen.
+//! Each access site passes its cache to a stub shared by the sites of the
+//! same kind, which compares an object's shape with the cached ones and reads
+//! or writes the slot directly on a hit. On a miss it calls a handler, which
+//! does the lookup and adds the shape to the cache, so the code patches itself
+//! to handle that shape without the handler from then on.
+//!
+//! Compiled code is mapped read-only, so what's patched is the cache that
+//! the code reads its shapes and slots from, rather than the code itself.
+
+use crate::{flow_graph::BasicBlockId, object::ShapeId};
+
+/// The number of shapes a cache holds. A cache with one shape is
+/// monomorphic, and one with more is polymorphic; once it's full, accesses
+/// to objects of other shapes keep missing.
+pub(crate) const INLINE_CACHE_ENTRIES: usize = 4;
+
+/// The shape of an unused entry, which no object has.
+const NO_SHAPE: u64 = u64::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PropertyAccess {
+    Load,
+    Store,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Entry {
+    shape: u64,
+    slot: u64,
+}
+
+/// The offsets of the fields of a cache, which compiled code reads and
+/// updates.
+pub(crate) const INLINE_CACHE_ENTRY_SIZE: usize = 16;
+pub(crate) const INLINE_CACHE_SLOT_OFFSET: usize = 8;
+pub(crate) const INLINE_CACHE_HITS_OFFSET: usize = INLINE_CACHE_ENTRY_SIZE * INLINE_CACHE_ENTRIES;
+
+/// The inline cache of one property access site.
+#[repr(C)]
+pub struct InlineCache {
+    entries: [Entry; INLINE_CACHE_ENTRIES],
+    hits: u64,
+    misses: u64,
+
+    basic_block_id: BasicBlockId,
+    access: PropertyAccess,
+    property: String,
+}
+
+impl InlineCache {
+    pub(crate) fn new(
+        basic_block_id: BasicBlockId,
+        access: PropertyAccess,
+        property: String,
+    ) -> Self {
+        Self {
+            entries: [Entry {
+                shape: NO_SHAPE,
+                slot: 0,
+            }; INLINE_CACHE_ENTRIES],
+            hits: 0,
+            misses: 0,
+            basic_block_id,
+            access,
+            property,
+        }
+    }
+
+    /// The block whose code the access site is in.
+    pub fn basic_block_id(&self) -> BasicBlockId {
+        self.basic_block_id
+    }
+
+    pub fn access(&self) -> PropertyAccess {
+        self.access
+    }
+
+    pub fn property(&self) -> &str {
+        &self.property
+    }
+
+    /// The number of accesses that found the object's shape in the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// The number of accesses that had to look the property up.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// The shapes the cache holds a slot for.
+    pub fn shapes(&self) -> Vec<ShapeId> {
+        self.entries
+            .iter()
+            .take_while(|entry| entry.shape != NO_SHAPE)
+            .map(|entry| ShapeId(entry.shape as usize))
+            .collect()
+    }
+
+    /// Counts a miss, and caches the slot of the property in objects of the
+    /// given shape, if there's one and there's still room.
+    pub(crate) fn record_miss(&mut self, shape_slot: Option<(ShapeId, usize)>) {
+        self.misses += 1;
+
+        let Some((shape, slot)) = shape_slot else {
+            return;
+        };
+
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.shape == NO_SHAPE)
+        {
+            *entry = Entry {
+                shape: shape.0 as u64,
+                slot: slot as u64,
+            };
+        }
+    }
+}
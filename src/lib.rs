@@ -1,15 +1,25 @@
-#![feature(int_roundings)]
-
 mod codegen;
 mod environment;
 mod flow_graph;
+mod inline_cache;
+mod nan_boxing;
+mod number;
+mod object;
+mod parser;
 mod source_location;
+mod string;
 
 pub use self::{
     codegen::CodegenStackEntry,
     environment::Environment,
     flow_graph::{
-        FlowGraph, Id, Scope, SystemFunction, SystemFunctionGeneratorFn, SystemFunctionHandlerFn,
-        Value,
+        BasicBlockId, FlowGraph, FlowInstruction, Id, Scope, SystemFunction,
+        SystemFunctionGeneratorFn, SystemFunctionHandlerFn, TailInstruction, Value,
     },
+    inline_cache::{InlineCache, PropertyAccess},
+    nan_boxing::{box_boolean, box_number, JsValue, FALSE, NULL, TRUE, UNDEFINED},
+    number::number_to_string,
+    object::ShapeId,
+    parser::parse_program,
+    string::string_value,
 };
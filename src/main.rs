@@ -1,6 +1,6 @@
 use omjit::{
-    CodegenStackEntry, Environment, FlowGraph, Id, Scope, SystemFunction, SystemFunctionHandlerFn,
-    Value,
+    parse_program, CodegenStackEntry, Environment, FlowGraph, Id, JsValue, Scope, SystemFunction,
+    SystemFunctionHandlerFn, Value, UNDEFINED,
 };
 use structopt::StructOpt;
 
@@ -14,6 +14,11 @@ struct Cli {
     #[structopt(short, long)]
     show_flowgraph: bool,
 
+    /// Print the hits and misses of each property access's inline cache
+    /// after executing program
+    #[structopt(short, long)]
+    inline_cache_stats: bool,
+
     /// The path to the file to read
     #[structopt(parse(from_os_str))]
     path: std::path::PathBuf,
@@ -33,24 +38,51 @@ fn main() {
     );
 
     let code = std::fs::read_to_string(args.path).expect("read source file");
-    let (_, node) = almond::parse_program(code.as_str().into()).expect("parse");
-    let flow_graph = FlowGraph::from_root_node(&node, scope);
+    let node = match parse_program(&code) {
+        Ok(node) => node,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+    let flow_graph = match FlowGraph::from_root_node(&node, scope) {
+        Ok(flow_graph) => flow_graph,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
 
     if args.show_flowgraph {
         println!("Flow graph: {:#?}", flow_graph);
     }
 
-    let environment = Environment::new(flow_graph, args.disassemble);
+    let mut environment = Environment::new(flow_graph, args.disassemble);
     environment.run().expect("run failed");
+
+    if args.inline_cache_stats {
+        for cache in environment.inline_caches() {
+            println!(
+                "{:?} {:?} of {:?}: {} hits, {} misses, {} shapes",
+                cache.basic_block_id(),
+                cache.access(),
+                cache.property(),
+                cache.hits(),
+                cache.misses(),
+                cache.shapes().len()
+            );
+        }
+    }
 }
 
 fn console_log_generator_fn(arguments: &[CodegenStackEntry]) -> Option<SystemFunctionHandlerFn> {
     match arguments {
-        [CodegenStackEntry::Number] => Some(console_log_integer_fn as SystemFunctionHandlerFn),
+        [_] => Some(console_log_fn as SystemFunctionHandlerFn),
         _ => None,
     }
 }
 
-extern "win64" fn console_log_integer_fn(value: u64) {
-    println!("{:?}", value);
+extern "win64" fn console_log_fn(value: u64) -> u64 {
+    println!("{}", JsValue::from_bits(value).to_display_string());
+    UNDEFINED
 }
@@ -0,0 +1,165 @@
+//! Compiled code holds every value in 64 bits. Numbers are doubles, stored
+//! as they are, and every other value is stored in the payload of a NaN with
+//! its sign bit set, which no arithmetic produces: the top 16 bits are a tag
+//! and the low 48 bits a pointer, if the value has one.
+
+use crate::{
+    number::{number_to_string, string_to_number},
+    string::{intern_string, string_value},
+};
+
+/// Values from here up are tagged, and the ones below are numbers.
+pub(crate) const FIRST_TAG: u64 = 0xfff9_0000_0000_0000;
+
+const TAG_MASK: u64 = 0xffff_0000_0000_0000;
+pub(crate) const PAYLOAD_MASK: u64 = !TAG_MASK;
+
+pub const UNDEFINED: u64 = 0xfff9_0000_0000_0000;
+pub const NULL: u64 = 0xfffa_0000_0000_0000;
+pub const FALSE: u64 = 0xfffb_0000_0000_0000;
+pub const TRUE: u64 = FALSE | 1;
+
+pub(crate) const TAG_STRING: u64 = 0xfffc_0000_0000_0000;
+pub(crate) const TAG_FUNCTION: u64 = 0xfffd_0000_0000_0000;
+pub(crate) const TAG_OBJECT: u64 = 0xfffe_0000_0000_0000;
+
+/// What a `let` or `const` binding holds until its declaration runs. Reading
+/// it raises an error, so it never ends up as the value of an expression.
+pub(crate) const UNINITIALIZED: u64 = 0xffff_0000_0000_0000;
+
+/// A value from compiled code, decoded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JsValue {
+    Undefined,
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(&'static str),
+
+    /// A function object, which holds the address of its code and its
+    /// environment record.
+    Function(u64),
+
+    /// A pointer to an `Object`.
+    Object(u64),
+}
+
+impl JsValue {
+    pub fn from_bits(bits: u64) -> Self {
+        if bits < FIRST_TAG {
+            return JsValue::Number(f64::from_bits(bits));
+        }
+
+        match bits & TAG_MASK {
+            UNDEFINED => JsValue::Undefined,
+            NULL => JsValue::Null,
+            FALSE => JsValue::Boolean(bits != FALSE),
+            TAG_STRING => JsValue::String(unsafe { string_value(bits) }),
+            TAG_FUNCTION => JsValue::Function(bits & PAYLOAD_MASK),
+            TAG_OBJECT => JsValue::Object(bits & PAYLOAD_MASK),
+            _ => panic!("invalid value {:#x}", bits),
+        }
+    }
+
+    /// Encodes the value. Strings are interned.
+    pub fn to_bits(self) -> u64 {
+        match self {
+            JsValue::Undefined => UNDEFINED,
+            JsValue::Null => NULL,
+            JsValue::Boolean(value) => box_boolean(value),
+            JsValue::Number(value) => box_number(value),
+            JsValue::String(value) => intern_string(value),
+            JsValue::Function(pointer) => TAG_FUNCTION | pointer,
+            JsValue::Object(pointer) => TAG_OBJECT | pointer,
+        }
+    }
+
+    /// The result of the `typeof` operator.
+    pub fn type_name(self) -> &'static str {
+        match self {
+            JsValue::Undefined => "undefined",
+            JsValue::Null | JsValue::Object(_) => "object",
+            JsValue::Boolean(_) => "boolean",
+            JsValue::Number(_) => "number",
+            JsValue::String(_) => "string",
+            JsValue::Function(_) => "function",
+        }
+    }
+
+    /// Converts the value to a number, as the unary `+` operator does.
+    pub fn to_number(self) -> f64 {
+        match self {
+            JsValue::Undefined | JsValue::Function(_) | JsValue::Object(_) => f64::NAN,
+            JsValue::Null => 0.0,
+            JsValue::Boolean(value) => value as u8 as f64,
+            JsValue::Number(value) => value,
+            JsValue::String(value) => string_to_number(value),
+        }
+    }
+
+    /// Converts the value to a string, as `String(value)` does. Functions
+    /// don't keep their source text, so they're all printed the same way.
+    pub fn to_display_string(self) -> String {
+        match self {
+            JsValue::Number(value) => number_to_string(value),
+            JsValue::String(value) => value.to_string(),
+            JsValue::Boolean(value) => value.to_string(),
+            JsValue::Function(_) => "[Function]".to_string(),
+            JsValue::Object(_) => "[object Object]".to_string(),
+            JsValue::Null => "null".to_string(),
+            JsValue::Undefined => "undefined".to_string(),
+        }
+    }
+
+    pub fn is_truthy(self) -> bool {
+        match self {
+            JsValue::Undefined | JsValue::Null => false,
+            JsValue::Boolean(value) => value,
+            JsValue::Number(value) => !(value == 0.0 || value.is_nan()),
+            JsValue::String(value) => !value.is_empty(),
+            JsValue::Function(_) | JsValue::Object(_) => true,
+        }
+    }
+
+    /// The `===` operator.
+    pub fn strict_equals(self, other: JsValue) -> bool {
+        match (self, other) {
+            (JsValue::Number(left), JsValue::Number(right)) => left == right,
+            (JsValue::String(left), JsValue::String(right)) => left == right,
+            (left, right) => left.to_bits() == right.to_bits(),
+        }
+    }
+
+    /// The `==` operator, which converts operands of different types to
+    /// numbers, except that `null` and `undefined` only equal each other.
+    pub fn loose_equals(self, other: JsValue) -> bool {
+        match (self, other) {
+            (JsValue::Null | JsValue::Undefined, JsValue::Null | JsValue::Undefined) => true,
+            (JsValue::Null | JsValue::Undefined, _) | (_, JsValue::Null | JsValue::Undefined) => {
+                false
+            }
+            (JsValue::Function(_) | JsValue::Object(_), _)
+            | (_, JsValue::Function(_) | JsValue::Object(_)) => self.strict_equals(other),
+            (left, right) if left.type_name() == right.type_name() => left.strict_equals(right),
+            (left, right) => left.to_number() == right.to_number(),
+        }
+    }
+}
+
+/// Encodes a number. Every NaN is stored as the same quiet NaN, so that none
+/// of them can be mistaken for a tagged value.
+pub fn box_number(value: f64) -> u64 {
+    if value.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        value.to_bits()
+    }
+}
+
+pub fn box_boolean(value: bool) -> u64 {
+    if value {
+        TRUE
+    } else {
+        FALSE
+    }
+}
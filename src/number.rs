@@ -0,0 +1,96 @@
+/// Formats a number the way JavaScript's `Number.prototype.toString` does:
+/// the shortest digits that round-trip, in exponent notation only for very
+/// large or very small magnitudes.
+pub fn number_to_string(value: f64) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+
+    if value == 0.0 {
+        // this includes -0
+        return "0".to_string();
+    }
+
+    if value.is_infinite() {
+        let sign = if value < 0.0 { "-" } else { "" };
+        return format!("{}Infinity", sign);
+    }
+
+    let sign = if value < 0.0 { "-" } else { "" };
+
+    // Rust prints the shortest round-tripping digits in exponent form, e.g.
+    // `1.25e-7`, which gives the digits and the exponent
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').expect("exponent");
+    let digits = mantissa.replace('.', "");
+    let exponent: i32 = exponent.parse().expect("exponent");
+
+    // the decimal point goes after the first `point` digits
+    let digit_count = digits.len() as i32;
+    let point = exponent + 1;
+
+    let formatted = if digit_count <= point && point <= 21 {
+        format!("{}{}", digits, "0".repeat((point - digit_count) as usize))
+    } else if 0 < point && point <= 21 {
+        let (whole, fraction) = digits.split_at(point as usize);
+        format!("{}.{}", whole, fraction)
+    } else if -6 < point && point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else {
+        let exponent_sign = if exponent < 0 { "-" } else { "+" };
+        let (first, rest) = digits.split_at(1);
+        let fraction = if rest.is_empty() {
+            String::new()
+        } else {
+            format!(".{}", rest)
+        };
+
+        format!("{}{}e{}{}", first, fraction, exponent_sign, exponent.abs())
+    };
+
+    format!("{}{}", sign, formatted)
+}
+
+/// Converts a string to a number the way JavaScript's `Number` does: the
+/// string is trimmed, an empty string is 0 and anything that isn't a decimal
+/// or hexadecimal literal, or `Infinity`, is NaN.
+pub fn string_to_number(value: &str) -> f64 {
+    let value = value.trim();
+    if value.is_empty() {
+        return 0.0;
+    }
+
+    if let Some(digits) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return f64::NAN;
+        }
+
+        return digits.chars().fold(0.0, |number, digit| {
+            number * 16.0 + digit.to_digit(16).unwrap() as f64
+        });
+    }
+
+    let (sign, unsigned) = match value.strip_prefix('-') {
+        Some(unsigned) => (-1.0, unsigned),
+        None => (1.0, value.strip_prefix('+').unwrap_or(value)),
+    };
+
+    if unsigned == "Infinity" {
+        return sign * f64::INFINITY;
+    }
+
+    // Rust also accepts `inf` and `nan`, which JavaScript doesn't
+    if !unsigned
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+    {
+        return f64::NAN;
+    }
+
+    unsigned
+        .parse::<f64>()
+        .map_or(f64::NAN, |number| sign * number)
+}
@@ -0,0 +1,136 @@
+//! Objects keep their property values in slots, and a shape, or hidden
+//! class, that maps property names to slots. Objects that got the same
+//! properties in the same order share a shape, so code that knows an
+//! object's shape knows which slot a property is in.
+
+use std::{collections::HashMap, mem::ManuallyDrop, sync::Mutex};
+
+use lazy_static::lazy_static;
+
+use crate::nan_boxing::{PAYLOAD_MASK, TAG_OBJECT, UNDEFINED};
+
+#[derive(Default)]
+struct Shape {
+    slots: HashMap<String, usize>,
+
+    /// The shapes that adding each property to an object of this shape
+    /// leads to. Following them rather than creating new shapes is what
+    /// makes objects built the same way share a shape.
+    transitions: HashMap<String, ShapeId>,
+}
+
+lazy_static! {
+    static ref SHAPES: Mutex<Vec<Shape>> = Mutex::new(vec![Shape::default()]);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ShapeId(pub(crate) usize);
+
+impl ShapeId {
+    /// The shape of an object without properties.
+    pub const EMPTY: ShapeId = ShapeId(0);
+
+    /// The slot that objects of this shape keep property `name` in.
+    pub fn slot(self, name: &str) -> Option<usize> {
+        SHAPES.lock().unwrap()[self.0].slots.get(name).copied()
+    }
+
+    pub fn property_count(self) -> usize {
+        SHAPES.lock().unwrap()[self.0].slots.len()
+    }
+
+    /// The shape an object of this shape gets when property `name` is added
+    /// to it, which it keeps if it already has the property.
+    pub fn with_property(self, name: &str) -> ShapeId {
+        let mut shapes = SHAPES.lock().unwrap();
+        let shape = &shapes[self.0];
+
+        if shape.slots.contains_key(name) {
+            return self;
+        }
+
+        if let Some(next) = shape.transitions.get(name) {
+            return *next;
+        }
+
+        let mut slots = shape.slots.clone();
+        slots.insert(name.to_owned(), slots.len());
+
+        let next = ShapeId(shapes.len());
+        shapes.push(Shape {
+            slots,
+            transitions: HashMap::new(),
+        });
+        shapes[self.0].transitions.insert(name.to_owned(), next);
+
+        next
+    }
+}
+
+/// The offsets of the fields of an object, which compiled code reads.
+pub(crate) const OBJECT_SHAPE_OFFSET: usize = 0;
+pub(crate) const OBJECT_SLOTS_OFFSET: usize = 8;
+
+/// An object, laid out for compiled code: its shape, and a pointer to its
+/// slots, which there's one of for each property of the shape.
+#[repr(C)]
+pub struct Object {
+    shape: ShapeId,
+    slots: *mut u64,
+    capacity: usize,
+}
+
+impl Object {
+    /// Allocates an object of shape `shape` whose properties are all
+    /// undefined, and returns it as an object value. Like function objects,
+    /// objects are never freed.
+    pub fn allocate(shape: ShapeId) -> u64 {
+        let slots = vec![UNDEFINED; shape.property_count()];
+        let mut slots = ManuallyDrop::new(slots);
+
+        let object = Box::new(Object {
+            shape,
+            slots: slots.as_mut_ptr(),
+            capacity: slots.capacity(),
+        });
+
+        TAG_OBJECT | Box::leak(object) as *mut Object as u64
+    }
+
+    /// The object that an object value points to.
+    ///
+    /// # Safety
+    ///
+    /// `value` must have been returned by `Object::allocate`.
+    pub unsafe fn from_value(value: u64) -> &'static mut Object {
+        unsafe { &mut *((value & PAYLOAD_MASK) as *mut Object) }
+    }
+
+    pub fn shape(&self) -> ShapeId {
+        self.shape
+    }
+
+    pub fn get(&self, name: &str) -> Option<u64> {
+        let slot = self.shape.slot(name)?;
+        Some(unsafe { *self.slots.add(slot) })
+    }
+
+    /// Sets property `name`, adding it if the object doesn't have it, which
+    /// moves the object to a new shape.
+    pub fn set(&mut self, name: &str, value: u64) {
+        if let Some(slot) = self.shape.slot(name) {
+            unsafe { *self.slots.add(slot) = value };
+            return;
+        }
+
+        let length = self.shape.property_count();
+        let mut slots = unsafe { Vec::from_raw_parts(self.slots, length, self.capacity) };
+        slots.push(value);
+
+        let mut slots = ManuallyDrop::new(slots);
+        self.slots = slots.as_mut_ptr();
+        self.capacity = slots.capacity();
+        self.shape = self.shape.with_property(name);
+    }
+}
@@ -0,0 +1,26 @@
+use almond::ast::Node;
+use anyhow::{bail, Result};
+
+/// Parses a program. The parser stops at the first statement it doesn't
+/// support, such as a `class` declaration, so any input left over
+/// is reported as an error instead of being silently dropped.
+pub fn parse_program(code: &str) -> Result<Node<'_>> {
+    let (rest, node) = match almond::parse_program(code.into()) {
+        Ok(result) => result,
+        Err(error) => bail!("SyntaxError: {:?}", error),
+    };
+
+    let unparsed = rest.fragment().trim_start();
+    if !unparsed.is_empty() {
+        let skipped = &rest.fragment()[..rest.fragment().len() - unparsed.len()];
+        let line = rest.location_line() as usize + skipped.matches('\n').count();
+
+        bail!(
+            "SyntaxError: Unsupported syntax on line {}: {}",
+            line,
+            unparsed.lines().next().unwrap_or_default()
+        );
+    }
+
+    Ok(node)
+}
@@ -0,0 +1,37 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use lazy_static::lazy_static;
+
+use crate::nan_boxing::{PAYLOAD_MASK, TAG_STRING};
+
+lazy_static! {
+    static ref INTERNED_STRINGS: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+}
+
+/// Interns `value`, and returns it as a string value. Compiled code holds a
+/// string as a tagged pointer to a `String` that lives for the rest of the
+/// program, and equal strings are interned to the same pointer.
+pub fn intern_string(value: &str) -> u64 {
+    let mut interned = INTERNED_STRINGS.lock().unwrap();
+    let pointer = *interned
+        .entry(value.to_owned())
+        .or_insert_with(|| Box::into_raw(Box::new(value.to_owned())) as usize);
+
+    TAG_STRING | pointer as u64
+}
+
+/// Allocates a string that compiled code creates, such as the result of a
+/// concatenation, on the string heap and returns it as a string value.
+/// Nothing tracks which strings are still in use, so they're never freed.
+pub fn allocate_string(value: String) -> u64 {
+    TAG_STRING | Box::into_raw(Box::new(value)) as u64
+}
+
+/// The string that a string value from compiled code points to.
+///
+/// # Safety
+///
+/// `value` must have been returned by `intern_string` or `allocate_string`.
+pub unsafe fn string_value(value: u64) -> &'static str {
+    unsafe { &*((value & PAYLOAD_MASK) as *const String) }
+}
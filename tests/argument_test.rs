@@ -0,0 +1,112 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_calls_with_more_than_four_arguments() {
+    let emitted = run("
+        function weigh(a, b, c, d, e, f) {
+            return a + 10 * b + 100 * c + 1000 * d + 10000 * e + 100000 * f;
+        }
+
+        __emit(weigh(1, 2, 3, 4, 5, 6));
+        __emit(weigh(6, 5, 4, 3, 2, 1));
+    ");
+
+    assert_eq!(emitted, ["654321", "123456"]);
+}
+
+#[test]
+fn test_stack_arguments_in_later_blocks() {
+    let emitted = run("
+        function repeat(a, b, c, d, times) {
+            var total = 0;
+            for (var i = 0; i < times; i++) {
+                total += a + b + c + d;
+            }
+            times = times * 2;
+            return total + times;
+        }
+
+        __emit(repeat(1, 2, 3, 4, 3));
+    ");
+
+    assert_eq!(emitted, ["36"]);
+}
+
+#[test]
+fn test_stack_arguments_in_recursive_and_indirect_calls() {
+    let emitted = run("
+        function sum(n, a, b, c, acc) {
+            if (n == 0) {
+                return acc;
+            }
+            return sum(n - 1, a, b, c, acc + a + b + c);
+        }
+
+        var f = function (a, b, c, d, e) {
+            function inner() {
+                return e - a;
+            }
+            return inner();
+        };
+
+        __emit(sum(3, 1, 2, 3, 0));
+        __emit(f(1, 2, 3, 4, 50));
+    ");
+
+    assert_eq!(emitted, ["18", "49"]);
+}
+
+#[test]
+fn test_calls_with_more_arguments_than_stack_registers() {
+    let emitted = run("
+        function m(a, b, c, d, e, f, g, h) {
+            return a + b + c + d + e + f + g + h;
+        }
+        var indirect = function (a, b, c, d, e, f, g) {
+            return a * b * c * d * e * f * g;
+        };
+
+        __emit(m(1, 2, 3, 4, 5, 6, 7, 8));
+        __emit(indirect(1, 2, 3, 4, 5, 6, 7));
+        __emit(m(1, 2, 3, 4, m(1, 2, 3, 4, 5, 6, 7, 8), 6, indirect(1, 1, 1, 1, 1, 1, 2), 8));
+        __emit(m(1, 2, 3, 4, 5, 6, 7, 8 > 7 ? 10 : 0));
+    ");
+
+    assert_eq!(emitted, ["36", "5040", "62", "38"]);
+}
+
+#[test]
+fn test_arguments_object() {
+    let emitted = run("
+        function count() {
+            return arguments.length;
+        }
+        function sum() {
+            var total = 0;
+            for (var i = 0; i < arguments.length; i++) {
+                total += arguments[i];
+            }
+            return total;
+        }
+        function second(a) {
+            return arguments[1];
+        }
+        function captured() {
+            var args = arguments;
+            return function () {
+                return args[0];
+            };
+        }
+
+        __emit(count());
+        __emit(count(1, 2, 3));
+        __emit(sum(1, 2, 3, 4, 5, 6, 7));
+        __emit(second(1, 'two'));
+        __emit(second(1));
+        __emit(captured('kept')());
+    ");
+
+    assert_eq!(emitted, ["0", "3", "28", "two", "undefined", "kept"]);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_fractional_arithmetic() {
+    let emitted = run("
+        __emit(0.5 + 0.25);
+        __emit(0.1 + 0.2);
+        __emit(1 - 3);
+        __emit(1.5 * 4);
+        __emit(7 / 2);
+        __emit(7 % 3);
+        __emit((0 - 7) % 3);
+        __emit(5.5 % 2);
+    ");
+
+    assert_eq!(
+        emitted,
+        [
+            "0.75",
+            "0.30000000000000004",
+            "-2",
+            "6",
+            "3.5",
+            "1",
+            "-1",
+            "1.5"
+        ]
+    );
+}
+
+#[test]
+fn test_special_values() {
+    let emitted = run("
+        __emit(1 / 0);
+        __emit((0 - 1) / 0);
+        __emit(0 / 0);
+        __emit(0 / (0 - 1));
+        __emit(1 / (0 / (0 - 1)));
+        __emit(0 / 0 == 0 / 0);
+        __emit(0 / 0 != 0 / 0);
+        __emit(0 / 0 < 1);
+        __emit(0 / 0 >= 1);
+        __emit(0 == 0 / (0 - 1));
+        __emit((0 - 2) < 1);
+    ");
+
+    assert_eq!(
+        emitted,
+        [
+            "Infinity",
+            "-Infinity",
+            "NaN",
+            "0",
+            "-Infinity",
+            "false",
+            "true",
+            "false",
+            "false",
+            "true",
+            "true"
+        ]
+    );
+}
+
+#[test]
+fn test_number_formatting() {
+    let emitted = run("
+        __emit(1000000 * 1000000 * 1000000 * 100);
+        __emit(1000000 * 1000000 * 1000000 * 1000);
+        __emit(1 / 1000000);
+        __emit(1 / 10000000);
+        __emit(0 - 1.25 / 10000000);
+    ");
+
+    assert_eq!(
+        emitted,
+        [
+            "100000000000000000000",
+            "1e+21",
+            "0.000001",
+            "1e-7",
+            "-1.25e-7"
+        ]
+    );
+}
+
+#[test]
+fn test_numbers_in_branches() {
+    let emitted = run("
+        function describe(x) {
+            if (x) {
+                return 1;
+            }
+            return 0;
+        }
+
+        __emit(describe(0.5));
+        __emit(describe(0));
+        __emit(describe(0 / (0 - 1)));
+        __emit(describe(0 / 0));
+        __emit(describe(0 - 3));
+    ");
+
+    assert_eq!(emitted, ["1", "0", "0", "0", "1"]);
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_missing_arguments_are_undefined() {
+    let emitted = run("
+        function given(a, b, c) {
+            return c ? 1 : 0;
+        }
+
+        function fifth_given(a, b, c, d, e) {
+            return e ? 1 : 0;
+        }
+
+        __emit(given(1));
+        __emit(given(1, 2, 3));
+        __emit(fifth_given(1, 2, 3));
+        __emit(fifth_given(1, 2, 3, 4, 5));
+    ");
+
+    assert_eq!(emitted, ["0", "1", "0", "1"]);
+}
+
+#[test]
+fn test_extra_arguments_are_evaluated_and_ignored() {
+    let emitted = run("
+        var calls = 0;
+
+        function count() {
+            calls++;
+            return calls;
+        }
+
+        function first(a) {
+            return a;
+        }
+
+        __emit(first(10, count(), count()));
+        __emit(calls);
+
+        var f = first;
+        __emit(f(20, count(), 30, 40, 50));
+        __emit(calls);
+    ");
+
+    assert_eq!(emitted, ["10", "2", "20", "3"]);
+}
+
+#[test]
+fn test_missing_arguments_in_indirect_calls() {
+    let emitted = run("
+        var f = function (a, b, c, d, e, g) {
+            return g ? a + g : a + b;
+        };
+
+        __emit(f(1, 2));
+        __emit(f(1, 2, 3, 4, 5, 6));
+    ");
+
+    assert_eq!(emitted, ["3", "7"]);
+}
+
+#[test]
+fn test_system_function_arity() {
+    let emitted = run("
+        __emit();
+        __emit(1, 2);
+        __emit(__sum(1, 2));
+        __emit(__sum(1, 2, 3));
+        __emit(__sum(1, 2, 3, 4));
+    ");
+
+    assert_eq!(emitted, ["undefined", "1", "3", "6", "10"]);
+}
+
+#[test]
+fn test_system_function_with_stack_arguments() {
+    let emitted = run("
+        function ten() {
+            return 10;
+        }
+
+        __emit(__sum(1, 2, 3, 4, 5, 6));
+        __emit(__sum(1, 2, 3, 4, ten(), ten() > 5 ? 100 : 0));
+    ");
+
+    assert_eq!(emitted, ["21", "120"]);
+}
+
+#[test]
+fn test_default_parameters() {
+    let emitted = run("
+        function f(a, b = a + 1, c = 'c') {
+            return a + ',' + b + ',' + c;
+        }
+        var count = 0;
+        function next() {
+            count++;
+            return count;
+        }
+        function g(x = next()) {
+            return x;
+        }
+        var h = (x, y = () => x * 2) => y();
+
+        __emit(f(1));
+        __emit(f(1, 5, 6));
+        __emit(f(1, undefined, null));
+        __emit(g(10));
+        __emit(g());
+        __emit(g());
+        __emit(h(4));
+    ");
+
+    assert_eq!(emitted, ["1,2,c", "1,5,6", "1,2,null", "10", "1", "2", "8"]);
+}
+
+#[test]
+fn test_rest_parameters() {
+    let emitted = run("
+        function f(a, ...rest) {
+            var sum = 0;
+            for (var i = 0; i < rest.length; i++) {
+                sum += rest[i];
+            }
+            return a + ':' + rest.length + ':' + sum;
+        }
+        var g = (...all) => all.length;
+
+        __emit(f(1));
+        __emit(f(1, 2, 3));
+        __emit(f(1, 2, 3, 4, 5, 6, 7));
+        __emit(g());
+        __emit(g(1, 2));
+    ");
+
+    assert_eq!(emitted, ["1:0:0", "1:2:5", "1:6:27", "0", "2"]);
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_assignment_evaluates_to_the_value() {
+    let emitted = run("
+        var a = 1;
+        var b = 2;
+        __emit(a = 5);
+        __emit(a);
+        a = b = 7;
+        __emit(a);
+        __emit(b);
+        __emit((a = 3) + 1);
+    ");
+
+    assert_eq!(emitted, ["5", "5", "7", "7", "4"]);
+}
+
+#[test]
+fn test_arithmetic_compound_assignment() {
+    let emitted = run("
+        var x = 10;
+        __emit(x += 5);
+        __emit(x -= 3);
+        __emit(x *= 2);
+        __emit(x /= 8);
+        __emit(x %= 2);
+        x = 2;
+        __emit(x **= 10);
+        __emit(x - 1000);
+    ");
+
+    assert_eq!(emitted, ["15", "12", "24", "3", "1", "1024", "24"]);
+}
+
+#[test]
+fn test_bitwise_compound_assignment() {
+    let emitted = run("
+        var x = 12;
+        __emit(x |= 3);
+        __emit(x &= 6);
+        __emit(x ^= 5);
+        __emit(x <<= 4);
+        __emit(x >>= 2);
+        x = 0 - 16;
+        __emit(x >> 2);
+        __emit(x >>>= 28);
+        __emit(1 << 33);
+        __emit(5.9 | 0);
+        __emit((0 - 5.9) | 0);
+        __emit(4294967296 + 7 | 0);
+    ");
+
+    assert_eq!(
+        emitted,
+        ["15", "6", "3", "48", "12", "-4", "15", "2", "5", "-5", "7"]
+    );
+}
+
+#[test]
+fn test_exponentiation() {
+    let emitted = run("
+        __emit(2 ** 0.5 * 2 ** 0.5);
+        __emit((0 / 0) ** 0);
+        __emit(1 ** (0 / 0));
+        __emit(1 ** (1 / 0));
+        __emit(2 ** (0 - 1));
+    ");
+
+    assert_eq!(emitted, ["2.0000000000000004", "1", "NaN", "NaN", "0.5"]);
+}
+
+#[test]
+fn test_assignment_to_parameters() {
+    let emitted = run("
+        function sum(n) {
+            for (var i = 0, s = 0; i < n; i++) {
+                s += i;
+            }
+            return s;
+        }
+
+        function twice(x) {
+            x *= 2;
+            return x;
+        }
+
+        __emit(sum(5));
+        __emit(twice(21));
+    ");
+
+    assert_eq!(emitted, ["10", "42"]);
+}
+
+#[test]
+fn test_logical_assignment() {
+    let emitted = run("
+        function side_effect(x) {
+            __emit('evaluated');
+            return x;
+        }
+
+        var a = 0;
+        var b = 1;
+        var c;
+        __emit(a ||= 5);
+        __emit(b ||= side_effect(6));
+        __emit(a &&= 7);
+        __emit(c &&= side_effect(8));
+        __emit(c ??= 9);
+        __emit(c ??= side_effect(10));
+        __emit(a + ',' + b + ',' + c);
+
+        var o = { x: 0, y: null };
+        var key = 'z';
+        __emit(o.x ||= 'x');
+        __emit(o['y'] ??= 'y');
+        __emit(o[key] &&= side_effect('z'));
+        __emit(o.x ||= side_effect('unused'));
+        __emit(o.x + ',' + o.y + ',' + o.z);
+    ");
+
+    assert_eq!(
+        emitted,
+        [
+            "5",
+            "1",
+            "7",
+            "undefined",
+            "9",
+            "9",
+            "7,1,9",
+            "x",
+            "y",
+            "undefined",
+            "x",
+            "x,y,undefined"
+        ]
+    );
+}
+
+#[test]
+fn test_logical_assignment_only_stores_when_evaluating_the_value() {
+    let emitted = run("
+        const c = 1;
+        let l;
+        function f() {
+            return c ||= 2;
+        }
+        __emit(f());
+        __emit(l ??= 3);
+        __emit(l);
+    ");
+
+    assert_eq!(emitted, ["1", "3", "3"]);
+}
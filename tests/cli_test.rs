@@ -0,0 +1,146 @@
+use std::process::{Command, Output};
+
+/// Runs the `omjit` binary on `code`, which is written to a file of its own
+/// for the test `name`.
+fn run_binary(name: &str, code: &str) -> Output {
+    let path = std::env::temp_dir().join(format!("omjit_cli_test_{}.js", name));
+    std::fs::write(&path, code).expect("write source file");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_omjit"))
+        .arg(&path)
+        .output()
+        .expect("run omjit");
+
+    std::fs::remove_file(&path).expect("remove source file");
+    output
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn test_runs_program() {
+    let output = run_binary("runs_program", "__console_log(1 + 2);");
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+}
+
+#[test]
+fn test_parse_errors_exit_with_status_1() {
+    let output = run_binary("parse_error", "var = ;");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("SyntaxError"));
+}
+
+#[test]
+fn test_compile_errors_exit_with_status_1() {
+    let output = run_binary("compile_error", "while (1) { break nope; }");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), "SyntaxError: Undefined label 'nope'\n");
+}
+
+#[test]
+fn test_reading_bindings_before_their_declaration() {
+    for (name, code) in [
+        ("tdz_read", "__console_log(x); let x = 1;"),
+        ("tdz_initializer", "let x = x;"),
+        ("tdz_assignment", "{ x = 2; let x; }"),
+        (
+            "tdz_closure",
+            "function f() { return x; } f(); const x = 1;",
+        ),
+    ] {
+        let output = run_binary(name, code);
+
+        assert_eq!(output.status.code(), Some(1), "{}", code);
+        assert_eq!(
+            stderr(&output),
+            "ReferenceError: Cannot access 'x' before initialization\n",
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+fn test_assignment_to_const_from_a_closure() {
+    let output = run_binary(
+        "const_closure",
+        "const c = 1; function f() { c = 2; } __console_log(c); f();",
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    assert_eq!(stderr(&output), "TypeError: Assignment to constant variable.\n");
+}
+
+#[test]
+fn test_calling_values_that_are_not_functions() {
+    for (name, code, message) in [
+        ("call_number", "(1)();", "TypeError: 1 is not a function\n"),
+        ("call_string", "('s')();", "TypeError: s is not a function\n"),
+        (
+            "call_object",
+            "({})();",
+            "TypeError: [object Object] is not a function\n",
+        ),
+        (
+            "call_variable",
+            "var f = null; f();",
+            "TypeError: null is not a function\n",
+        ),
+    ] {
+        let output = run_binary(name, code);
+
+        assert_eq!(output.status.code(), Some(1), "{}", code);
+        assert_eq!(stderr(&output), message, "{}", code);
+    }
+}
+
+#[test]
+fn test_property_access_on_undefined_and_null() {
+    for (name, code, message) in [
+        (
+            "get_undefined",
+            "var o; o.x;",
+            "TypeError: Cannot read properties of undefined (reading 'x')\n",
+        ),
+        (
+            "get_null",
+            "var o = null; o['y'];",
+            "TypeError: Cannot read properties of null (reading 'y')\n",
+        ),
+        (
+            "set_undefined",
+            "var o; o.x = 1;",
+            "TypeError: Cannot set properties of undefined (setting 'x')\n",
+        ),
+    ] {
+        let output = run_binary(name, code);
+
+        assert_eq!(output.status.code(), Some(1), "{}", code);
+        assert_eq!(stderr(&output), message, "{}", code);
+    }
+}
+
+#[test]
+fn test_system_functions_can_only_be_called() {
+    for (name, code) in [
+        ("system_function_value", "var log = __console_log;"),
+        ("system_function_assignment", "__console_log = 1;"),
+    ] {
+        let output = run_binary(name, code);
+
+        assert_eq!(output.status.code(), Some(1), "{}", code);
+        assert_eq!(
+            stderr(&output),
+            "SyntaxError: System function '__console_log' can only be called\n",
+            "{}",
+            code
+        );
+    }
+}
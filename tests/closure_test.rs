@@ -0,0 +1,123 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_nested_function_reads_outer_variables() {
+    let emitted = run("
+        function outer(x) {
+            var y = 10;
+            function inner(z) {
+                return x + y + z;
+            }
+            return inner(100);
+        }
+
+        __emit(outer(1));
+        __emit(outer(2));
+    ");
+
+    assert_eq!(emitted, ["111", "112"]);
+}
+
+#[test]
+fn test_nested_function_mutates_outer_variables() {
+    let emitted = run("
+        function counter(n) {
+            var count = 0;
+            function bump(by) {
+                count += by;
+                n--;
+                return count;
+            }
+            bump(2);
+            bump(3);
+            return count * 100 + n;
+        }
+
+        __emit(counter(10));
+    ");
+
+    assert_eq!(emitted, ["508"]);
+}
+
+#[test]
+fn test_functions_see_program_variables() {
+    let emitted = run("
+        var total = 0;
+
+        function add(x) {
+            total = total + x;
+        }
+
+        for (var i = 1; i <= 4; i++) {
+            add(i);
+        }
+
+        __emit(total);
+    ");
+
+    assert_eq!(emitted, ["10"]);
+}
+
+#[test]
+fn test_captures_across_several_functions() {
+    let emitted = run("
+        var base = 1000;
+
+        function a(x) {
+            function b(y) {
+                function c(z) {
+                    base = base + 1;
+                    return base + x + y + z;
+                }
+                return c(3);
+            }
+            return b(20);
+        }
+
+        __emit(a(100));
+        __emit(base);
+    ");
+
+    assert_eq!(emitted, ["1124", "1001"]);
+}
+
+#[test]
+fn test_each_call_gets_its_own_environment() {
+    let emitted = run("
+        function sum(n) {
+            function rest() {
+                return sum(n - 1);
+            }
+            if (n == 0) {
+                return 0;
+            }
+            return n + rest();
+        }
+
+        __emit(sum(5));
+    ");
+
+    assert_eq!(emitted, ["15"]);
+}
+
+#[test]
+fn test_inner_variables_shadow_captured_ones() {
+    let emitted = run("
+        var x = 1;
+
+        function shadow() {
+            var x = 2;
+            function inner() {
+                return x;
+            }
+            return inner();
+        }
+
+        __emit(shadow());
+        __emit(x);
+    ");
+
+    assert_eq!(emitted, ["2", "1"]);
+}
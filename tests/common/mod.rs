@@ -0,0 +1,86 @@
+use std::cell::RefCell;
+
+use omjit::{
+    box_number, parse_program, Environment, FlowGraph, Id, JsValue, Scope, SystemFunction,
+    SystemFunctionHandlerFn, Value, UNDEFINED,
+};
+
+thread_local! {
+    static EMITTED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn emit(value: String) {
+    EMITTED.with(|emitted| emitted.borrow_mut().push(value));
+}
+
+extern "win64" fn emit_fn(value: u64) -> u64 {
+    emit(JsValue::from_bits(value).to_display_string());
+    UNDEFINED
+}
+
+fn number(value: u64) -> f64 {
+    JsValue::from_bits(value).to_number()
+}
+
+extern "win64" fn sum_two_fn(a: u64, b: u64) -> u64 {
+    box_number(number(a) + number(b))
+}
+
+extern "win64" fn sum_three_fn(a: u64, b: u64, c: u64) -> u64 {
+    box_number(number(a) + number(b) + number(c))
+}
+
+extern "win64" fn sum_four_fn(a: u64, b: u64, c: u64, d: u64) -> u64 {
+    box_number(number(a) + number(b) + number(c) + number(d))
+}
+
+extern "win64" fn sum_six_fn(a: u64, b: u64, c: u64, d: u64, e: u64, f: u64) -> u64 {
+    box_number(number(a) + number(b) + number(c) + number(d) + number(e) + number(f))
+}
+
+/// Compiles and runs `code`, returning every value passed to `__emit`,
+/// formatted as JavaScript would print it.
+// each test crate has its own copy of this module, and may not use this
+#[allow(dead_code)]
+pub fn run(code: &str) -> Vec<String> {
+    run_and_inspect(code, |_environment| {})
+}
+
+/// Like `run`, and passes the environment the code ran in to `inspect`
+/// afterwards.
+pub fn run_and_inspect(code: &str, inspect: impl FnOnce(&Environment)) -> Vec<String> {
+    let mut scope = Scope::default();
+    scope.insert(
+        Id("__emit".to_string()),
+        Value::SystemFunction(SystemFunction::new(
+            "emit".to_string(),
+            1,
+            Box::new(|args| match args {
+                [_] => Some(emit_fn as SystemFunctionHandlerFn),
+                _ => None,
+            }),
+        )),
+    );
+
+    scope.insert(
+        Id("__sum".to_string()),
+        Value::SystemFunction(SystemFunction::variadic(
+            "sum".to_string(),
+            Box::new(|args| match args.len() {
+                2 => Some(sum_two_fn as SystemFunctionHandlerFn),
+                3 => Some(sum_three_fn as SystemFunctionHandlerFn),
+                4 => Some(sum_four_fn as SystemFunctionHandlerFn),
+                6 => Some(sum_six_fn as SystemFunctionHandlerFn),
+                _ => None,
+            }),
+        )),
+    );
+
+    let node = parse_program(code).expect("parse");
+    let flow_graph = FlowGraph::from_root_node(&node, scope).expect("compile");
+    let mut environment = Environment::new(flow_graph, false);
+    environment.run().expect("run failed");
+    inspect(&environment);
+
+    EMITTED.with(|emitted| emitted.take())
+}
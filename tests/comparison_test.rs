@@ -0,0 +1,67 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_relational_operators() {
+    let emitted = run("
+        __emit(1 < 2);
+        __emit(2 < 1);
+        __emit(2 <= 2);
+        __emit(3 > 2);
+        __emit(2 >= 3);
+    ");
+
+    assert_eq!(emitted, vec!["true", "false", "true", "true", "false"]);
+}
+
+#[test]
+fn test_equality_operators() {
+    let emitted = run("
+        __emit(2 == 2);
+        __emit(2 != 2);
+        __emit(2 === 2);
+        __emit(2 !== 3);
+        __emit((1 < 2) === (2 < 3));
+        __emit((1 < 2) == 1);
+        __emit((1 < 2) === 1);
+        __emit((1 < 2) !== 1);
+    ");
+
+    assert_eq!(
+        emitted,
+        vec!["true", "false", "true", "true", "true", "true", "false", "true"]
+    );
+}
+
+#[test]
+fn test_comparisons_in_branches() {
+    let emitted = run("
+        function max(a, b) {
+            if (a > b) {
+                return a;
+            }
+            return b;
+        }
+
+        function first_below(a, b) {
+            while (a < b) {
+                return a;
+            }
+            return 0;
+        }
+
+        __emit(max(3, 7));
+        __emit(max(9, 4));
+        __emit(first_below(5, 6));
+        __emit(first_below(6, 5));
+
+        var x = 4;
+        var y = 4;
+        if (x == y) {
+            __emit(x + y);
+        }
+    ");
+
+    assert_eq!(emitted, vec!["7", "9", "5", "0", "8"]);
+}
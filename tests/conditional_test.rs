@@ -0,0 +1,51 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_conditional_expression() {
+    let emitted = run("
+        function sign(x) {
+            return x < 0 ? -1 : x > 0 ? 1 : 0;
+        }
+
+        __emit(sign(0 - 5));
+        __emit(sign(5));
+        __emit(sign(0));
+        __emit(1 < 2 ? 10 : 20);
+        __emit(0 ? 10 : 20);
+    ");
+
+    assert_eq!(emitted, ["-1", "1", "0", "10", "20"]);
+}
+
+#[test]
+fn test_conditional_expression_only_evaluates_one_arm() {
+    let emitted = run("
+        function side_effect(x) {
+            __emit(x);
+            return x;
+        }
+
+        __emit(1 ? side_effect(2) : side_effect(3));
+        __emit(0 ? side_effect(4) : side_effect(5));
+    ");
+
+    assert_eq!(emitted, ["2", "2", "5", "5"]);
+}
+
+#[test]
+fn test_conditional_arms_of_different_types() {
+    let emitted = run("
+        function describe(x) {
+            __emit(x > 1 ? 'big' : x);
+            __emit(x > 1 ? x : x < 1);
+            __emit(typeof (x > 1 ? 'big' : x));
+        }
+
+        describe(5);
+        describe(0);
+    ");
+
+    assert_eq!(emitted, ["big", "5", "string", "0", "true", "number"]);
+}
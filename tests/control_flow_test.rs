@@ -0,0 +1,170 @@
+mod common;
+
+use common::run;
+use omjit::{parse_program, FlowGraph, Scope};
+
+fn compile_error(code: &str) -> String {
+    let node = parse_program(code).expect("parse");
+    FlowGraph::from_root_node(&node, Scope::default())
+        .expect_err("compiled successfully")
+        .to_string()
+}
+
+#[test]
+fn test_conditional_jump_on_falsy_value() {
+    let code = "
+        function skip(x) {
+            for (; x; ) { __emit(1); }
+            __emit(x + 2);
+            return x;
+        }
+
+        for (; 0; ) { __emit(1); }
+        skip(0);
+        __emit(3);
+    ";
+
+    assert_eq!(run(code), ["2", "3"]);
+}
+
+#[test]
+fn test_if_else() {
+    let code = "
+        function pick(a, b) {
+            if (a) {
+                __emit(10);
+            } else if (b) {
+                __emit(20);
+            } else {
+                __emit(30);
+            }
+
+            if (b) __emit(40);
+            return 0;
+        }
+
+        pick(1, 0);
+        pick(0, 1);
+        pick(0, 0);
+    ";
+
+    assert_eq!(run(code), ["10", "20", "40", "30"]);
+}
+
+#[test]
+fn test_return_from_one_branch() {
+    let code = "
+        function choose(x, y) {
+            if (x) {
+                return y + 1;
+            }
+
+            __emit(y);
+            return y + 2;
+        }
+
+        __emit(choose(1, 10));
+        __emit(choose(0, 20));
+    ";
+
+    assert_eq!(run(code), ["11", "20", "22"]);
+}
+
+#[test]
+fn test_while_and_do_while() {
+    let code = "
+        function first(x) {
+            while (x) {
+                return x + 1;
+            }
+            return 0;
+        }
+
+        while (0) { __emit(1); }
+        do { __emit(2); } while (0);
+
+        __emit(first(5));
+        __emit(first(0));
+    ";
+
+    assert_eq!(run(code), ["2", "6", "0"]);
+}
+
+#[test]
+fn test_break_and_continue() {
+    let code = "
+        for (;;) {
+            __emit(1);
+            break;
+            __emit(0);
+        }
+
+        do {
+            __emit(2);
+            if (1) continue;
+            __emit(0);
+        } while (0);
+
+        while (1) {
+            if (0) {
+                continue;
+            } else {
+                break;
+            }
+        }
+
+        __emit(3);
+    ";
+
+    assert_eq!(run(code), ["1", "2", "3"]);
+}
+
+#[test]
+fn test_labeled_break_and_continue() {
+    let code = "
+        outer: for (;;) {
+            while (1) {
+                __emit(1);
+                break outer;
+            }
+            __emit(0);
+        }
+
+        outer: do {
+            do {
+                __emit(2);
+                continue outer;
+            } while (1);
+        } while (0);
+
+        block: {
+            __emit(3);
+            break block;
+            __emit(0);
+        }
+
+        __emit(4);
+    ";
+
+    assert_eq!(run(code), ["1", "2", "3", "4"]);
+}
+
+#[test]
+fn test_unresolvable_jumps_are_compile_errors() {
+    assert_eq!(
+        compile_error("for (;;) { break nowhere; }"),
+        "SyntaxError: Undefined label 'nowhere'"
+    );
+    assert_eq!(
+        compile_error("break;"),
+        "SyntaxError: Illegal break statement"
+    );
+    assert_eq!(
+        compile_error("for (;;) { function f() { continue; } }"),
+        "SyntaxError: Illegal continue statement: no surrounding iteration statement"
+    );
+    assert_eq!(
+        compile_error("block: { for (;;) { continue block; } }"),
+        "SyntaxError: Illegal continue statement: 'block' does not denote an iteration statement"
+    );
+}
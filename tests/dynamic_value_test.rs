@@ -0,0 +1,82 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_booleans_through_variables_and_calls() {
+    let emitted = run("
+        function positive(x) {
+            return x > 0;
+        }
+
+        var flag = positive(3);
+        __emit(flag);
+        __emit(positive(-1));
+        __emit(!flag);
+        __emit(flag + 1);
+        __emit(typeof flag);
+    ");
+
+    assert_eq!(emitted, ["true", "false", "false", "2", "boolean"]);
+}
+
+#[test]
+fn test_strings_through_variables_and_calls() {
+    let emitted = run("
+        function pick(first, a, b) {
+            return first ? a : b;
+        }
+
+        var empty = '';
+        __emit(pick(1, 'yes', 'no'));
+        __emit(pick(empty, 'yes', 'no'));
+        __emit(pick(0, 1, 'no') * 2);
+        __emit(pick(1, '21', 0) * 2);
+    ");
+
+    assert_eq!(emitted, ["yes", "no", "NaN", "42"]);
+}
+
+#[test]
+fn test_typeof_dynamic_values() {
+    let emitted = run("
+        var number = 1, string = 'x', missing;
+        var f = function () {};
+
+        __emit(typeof number);
+        __emit(typeof string);
+        __emit(typeof missing);
+        __emit(typeof f);
+        __emit(typeof f());
+    ");
+
+    assert_eq!(
+        emitted,
+        ["number", "string", "undefined", "function", "undefined"]
+    );
+}
+
+#[test]
+fn test_comparing_values_of_different_types() {
+    let emitted = run("
+        function id(x) {
+            return x;
+        }
+
+        var missing;
+        __emit(id(1) == '1');
+        __emit(id(1) === '1');
+        __emit(id(1) != ' 1 ');
+        __emit(missing == id(0));
+        __emit(missing === id());
+        __emit(id('b') > id('a'));
+        __emit(id('10') < id('9'));
+        __emit(id('10') < 9);
+        __emit(id(0 / 0) == id(0 / 0));
+    ");
+
+    assert_eq!(
+        emitted,
+        ["true", "false", "false", "false", "true", "true", "true", "false", "false"]
+    );
+}
@@ -0,0 +1,129 @@
+use omjit::{parse_program, BasicBlockId, FlowGraph, FlowInstruction, Scope, TailInstruction};
+
+fn tails(flow_graph: &FlowGraph, basic_block_id: BasicBlockId) -> Vec<TailInstruction> {
+    flow_graph
+        .get_basic_block(&basic_block_id)
+        .expect("missing basic block")
+        .tails
+        .clone()
+}
+
+fn jump_target(tail: &TailInstruction) -> BasicBlockId {
+    match tail {
+        TailInstruction::Jump(target) => *target,
+        other => panic!("expected jump, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_for_loop_back_edge() {
+    let code = "var s = 0; for (var i = 0; i < 3; i += 1) { s += i; } s;";
+
+    let node = parse_program(code).expect("parse");
+    let flow_graph = FlowGraph::from_root_node(&node, Scope::default()).expect("compile");
+    let root = flow_graph.root_block_id.expect("no root block");
+
+    let root_tails = tails(&flow_graph, root);
+    assert_eq!(root_tails.len(), 1);
+    let header = jump_target(&root_tails[0]);
+
+    let header_tails = tails(&flow_graph, header);
+    let (body, exit) = match &header_tails[..] {
+        [TailInstruction::ConditionalJump {
+            then_block,
+            else_block,
+        }] => (*then_block, *else_block),
+        other => panic!("unexpected loop header tails {:?}", other),
+    };
+
+    let update = jump_target(&tails(&flow_graph, body)[0]);
+    assert_eq!(jump_target(&tails(&flow_graph, update)[0]), header);
+
+    let exit_block = flow_graph.get_basic_block(&exit).unwrap();
+    assert!(matches!(
+        exit_block.instructions.last(),
+        Some(FlowInstruction::Return)
+    ));
+
+    let epilogue = jump_target(&tails(&flow_graph, exit)[0]);
+    let epilogue_block = flow_graph.get_basic_block(&epilogue).unwrap();
+    assert!(epilogue_block.tails.is_empty());
+    assert!(matches!(
+        epilogue_block.instructions[..],
+        [FlowInstruction::FunctionEpilogue]
+    ));
+}
+
+#[test]
+fn test_for_loop_without_clauses() {
+    let code = "for (;;) { 1; }";
+
+    let node = parse_program(code).expect("parse");
+    let flow_graph = FlowGraph::from_root_node(&node, Scope::default()).expect("compile");
+    let root = flow_graph.root_block_id.expect("no root block");
+
+    let header = jump_target(&tails(&flow_graph, root)[0]);
+    let header_tails = tails(&flow_graph, header);
+    assert_eq!(header_tails.len(), 1);
+
+    let body = jump_target(&header_tails[0]);
+    let update = jump_target(&tails(&flow_graph, body)[0]);
+    assert_eq!(jump_target(&tails(&flow_graph, update)[0]), header);
+}
+
+#[test]
+fn test_while_loops_back_edges() {
+    let code = "while (1) { 2; } do { 3; } while (4);";
+
+    let node = parse_program(code).expect("parse");
+    let flow_graph = FlowGraph::from_root_node(&node, Scope::default()).expect("compile");
+    let root = flow_graph.root_block_id.expect("no root block");
+
+    let header = jump_target(&tails(&flow_graph, root)[0]);
+    let (body, exit) = match &tails(&flow_graph, header)[..] {
+        [TailInstruction::ConditionalJump {
+            then_block,
+            else_block,
+        }] => (*then_block, *else_block),
+        other => panic!("unexpected while header tails {:?}", other),
+    };
+    assert_eq!(jump_target(&tails(&flow_graph, body)[0]), header);
+
+    let do_body = jump_target(&tails(&flow_graph, exit)[0]);
+    let do_test = jump_target(&tails(&flow_graph, do_body)[0]);
+    match &tails(&flow_graph, do_test)[..] {
+        [TailInstruction::ConditionalJump { then_block, .. }] => {
+            assert_eq!(*then_block, do_body)
+        }
+        other => panic!("unexpected do-while test tails {:?}", other),
+    }
+}
+
+#[test]
+fn test_calls_to_unassigned_functions_are_direct() {
+    let code = "
+        function direct() { return 1; }
+        function indirect() { return 2; }
+        direct();
+        indirect();
+        indirect = direct;
+    ";
+
+    let node = parse_program(code).expect("parse");
+    let flow_graph = FlowGraph::from_root_node(&node, Scope::default()).expect("compile");
+    let root = flow_graph.root_block_id.expect("no root block");
+
+    let calls = flow_graph
+        .get_basic_block(&root)
+        .expect("missing basic block")
+        .instructions
+        .iter()
+        .filter_map(|instruction| match instruction {
+            FlowInstruction::CallFunction { .. } => Some("direct"),
+            FlowInstruction::CallValue { .. } => Some("value"),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(calls, ["direct", "value"]);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use common::run;
+use omjit::Scope;
+
+#[test]
+fn test_nested_scopes_allocate_from_the_function_frame() {
+    let program = Rc::new(RefCell::new(Scope::default()));
+    assert_eq!(program.borrow_mut().allocate_stack(8), 0);
+
+    let function = Rc::new(RefCell::new(Scope::new_function_scope(program.clone())));
+    assert_eq!(function.borrow_mut().allocate_stack(8), 0);
+
+    let loop_body = Rc::new(RefCell::new(Scope::new(Some(function.clone()))));
+    let nested_block = Scope::new(Some(loop_body.clone()));
+    assert_eq!(loop_body.borrow_mut().allocate_stack(8), 8);
+    assert_eq!(function.borrow_mut().allocate_stack(8), 16);
+
+    assert_eq!(function.borrow().frame_size(), 24);
+    assert_eq!(loop_body.borrow().frame_size(), 24);
+    assert_eq!(nested_block.frame_size(), 24);
+    assert_eq!(program.borrow().frame_size(), 8);
+}
+
+#[test]
+fn test_locals_across_loop_blocks() {
+    let emitted = run("
+        function table(rows, columns, a, b, c) {
+            var total = 0;
+            var cells = 0;
+            for (var i = 0; i < rows; i++) {
+                var row = 0;
+                for (var j = 0; j < columns; j++) {
+                    var cell = i * columns + j;
+                    row += cell;
+                    cells++;
+                }
+                total += row * c;
+            }
+            return total + cells + a + b;
+        }
+
+        var result = 0;
+        for (var k = 0; k < 3; k++) {
+            result += table(k + 1, 4, 1000, 20000, 2);
+        }
+        __emit(result);
+    ");
+
+    assert_eq!(emitted, ["63224"]);
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_function_expressions_in_variables() {
+    let emitted = run("
+        var square = function (x) {
+            return x * x;
+        };
+
+        function cube(x) {
+            return x * x * x;
+        }
+
+        var f = cube;
+        __emit(square(7));
+        __emit(f(3));
+        f = square;
+        __emit(f(3));
+    ");
+
+    assert_eq!(emitted, ["49", "27", "9"]);
+}
+
+#[test]
+fn test_functions_as_arguments() {
+    let emitted = run("
+        function apply_twice(f, x) {
+            return f(f(x));
+        }
+
+        function increment(x) {
+            return x + 1;
+        }
+
+        __emit(apply_twice(increment, 5));
+        __emit(apply_twice(function (x) { return x * 10; }, 2));
+    ");
+
+    assert_eq!(emitted, ["7", "200"]);
+}
+
+#[test]
+fn test_returned_closures_keep_their_environment() {
+    let emitted = run("
+        function make_counter(start) {
+            var count = start;
+            return function () {
+                count++;
+                return count;
+            };
+        }
+
+        var a = make_counter(0);
+        var b = make_counter(100);
+        a();
+        a();
+        __emit(a());
+        __emit(b());
+    ");
+
+    assert_eq!(emitted, ["3", "101"]);
+}
+
+#[test]
+fn test_named_function_expressions_can_recurse() {
+    let emitted = run("
+        var factorial = function fact(n) {
+            if (n <= 1) {
+                return 1;
+            }
+            return n * fact(n - 1);
+        };
+
+        __emit(factorial(5));
+        __emit(typeof fact);
+    ");
+
+    assert_eq!(emitted, ["120", "undefined"]);
+}
+
+#[test]
+fn test_immediately_invoked_function_expressions() {
+    let emitted = run("
+        __emit((function (x) { return x * 2; })(21));
+        __emit(typeof function () {});
+    ");
+
+    assert_eq!(emitted, ["42", "function"]);
+}
+
+#[test]
+fn test_arrow_functions() {
+    let emitted = run("
+        var double = x => x * 2;
+        var add = (a, b) => a + b;
+        var answer = () => { return 42; };
+        var nothing = () => {};
+        __emit(double(4));
+        __emit(add(1, 2));
+        __emit(answer());
+        __emit(nothing());
+        __emit(typeof double);
+        __emit(((x) => x + 1)(1));
+    ");
+
+    assert_eq!(emitted, ["8", "3", "42", "undefined", "function", "2"]);
+}
+
+#[test]
+fn test_arrow_functions_capture_their_environment() {
+    let emitted = run("
+        function counter() {
+            var count = 0;
+            return () => ++count;
+        }
+        var next = counter();
+        next();
+        __emit(next());
+
+        var adder = a => b => a + b;
+        __emit(adder(1)(2));
+
+        function sum() {
+            var total = 0;
+            var add = i => { total += arguments[i]; };
+            add(0);
+            add(1);
+            return total;
+        }
+        __emit(sum(3, 4));
+    ");
+
+    assert_eq!(emitted, ["2", "3", "7"]);
+}
+
+#[test]
+fn test_function_declarations_are_variables() {
+    let emitted = run("
+        function f() { return 1; }
+        var g = f;
+        __emit(g());
+
+        function h() { return 2; }
+        var before = h();
+        h = 3;
+        __emit(before);
+        __emit(h);
+
+        function replaced() { return 'old'; }
+        function replace() {
+            replaced = function () { return 'new'; };
+        }
+        __emit(replaced());
+        replace();
+        __emit(replaced());
+
+        var named = function self() {
+            self = 4;
+            return typeof self;
+        };
+        __emit(named());
+
+        function counter() {
+            var count = 0;
+            function next() { return ++count; }
+            next = next;
+            next();
+            return next();
+        }
+        __emit(counter());
+    ");
+
+    assert_eq!(emitted, ["1", "2", "3", "old", "new", "function", "2"]);
+}
+
+#[test]
+fn test_typeof_system_functions() {
+    let emitted = run("
+        __emit(typeof __emit);
+    ");
+
+    assert_eq!(emitted, ["function"]);
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_functions_are_callable_before_their_declaration() {
+    let emitted = run("
+        __emit(double(21));
+
+        function double(x) {
+            return twice(x);
+        }
+
+        function twice(x) {
+            return x + x;
+        }
+    ");
+
+    assert_eq!(emitted, ["42"]);
+}
+
+#[test]
+fn test_mutually_recursive_functions() {
+    let emitted = run("
+        function is_even(n) {
+            if (n == 0) {
+                return 1;
+            }
+            return is_odd(n - 1);
+        }
+
+        function is_odd(n) {
+            if (n == 0) {
+                return 0;
+            }
+            return is_even(n - 1);
+        }
+
+        __emit(is_even(10));
+        __emit(is_odd(7));
+        __emit(is_even(3));
+    ");
+
+    assert_eq!(emitted, ["1", "1", "0"]);
+}
+
+#[test]
+fn test_vars_are_hoisted_to_the_function() {
+    let emitted = run("
+        x = 5;
+        __emit(x);
+        var x;
+        __emit(x);
+
+        function last(n) {
+            for (var i = 0; i < n; i++) {
+                var seen = i;
+            }
+            if (n > 100) {
+                var unused = 1;
+            }
+            return seen + i;
+        }
+
+        __emit(last(4));
+    ");
+
+    assert_eq!(emitted, ["5", "5", "7"]);
+}
+
+#[test]
+fn test_var_redeclares_parameter() {
+    let emitted = run("
+        function f(x) {
+            var x;
+            __emit(x);
+            var x = x + 1;
+            return x;
+        }
+
+        __emit(f(1));
+    ");
+
+    assert_eq!(emitted, ["1", "2"]);
+}
+
+#[test]
+fn test_block_functions_are_callable_before_their_declaration() {
+    let emitted = run("
+        {
+            __emit(g());
+            function g() {
+                return 1;
+            }
+        }
+        for (var i = 0; i < 2; i++) {
+            let k = i * 10;
+            __emit(read() + h());
+            function read() {
+                return k;
+            }
+            function h() {
+                return 5;
+            }
+            h = function () {
+                return 7;
+            };
+        }
+    ");
+
+    assert_eq!(emitted, ["1", "5", "15"]);
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use common::run_and_inspect;
+use omjit::{Environment, PropertyAccess};
+
+fn caches(environment: &Environment, access: PropertyAccess, property: &str) -> Vec<(u64, u64)> {
+    environment
+        .inline_caches()
+        .filter(|cache| cache.access() == access && cache.property() == property)
+        .map(|cache| (cache.hits(), cache.misses()))
+        .collect()
+}
+
+#[test]
+fn test_monomorphic_site() {
+    let emitted = run_and_inspect(
+        "
+        function x(point) {
+            return point.x;
+        }
+
+        var total = 0;
+        for (var i = 0; i < 10; i++) {
+            total += x({ x: i, y: 0 });
+        }
+        __emit(total);
+        ",
+        |environment| {
+            // the first access misses, and the rest find the shape
+            assert_eq!(caches(environment, PropertyAccess::Load, "x"), [(9, 1)]);
+        },
+    );
+
+    assert_eq!(emitted, ["45"]);
+}
+
+#[test]
+fn test_polymorphic_site() {
+    let emitted = run_and_inspect(
+        "
+        function x(point) {
+            return point.x;
+        }
+
+        var shapes = { a: { x: 1 }, b: { y: 0, x: 2 }, c: { z: 0, y: 0, x: 3 } };
+        var total = 0;
+        for (var i = 0; i < 4; i++) {
+            total += x(shapes.a) + x(shapes.b) + x(shapes.c);
+        }
+        __emit(total);
+        ",
+        |environment| {
+            let cache = environment
+                .inline_caches()
+                .find(|cache| cache.property() == "x")
+                .expect("no cache for x");
+
+            assert_eq!(cache.shapes().len(), 3);
+            assert_eq!((cache.hits(), cache.misses()), (9, 3));
+        },
+    );
+
+    assert_eq!(emitted, ["24"]);
+}
+
+#[test]
+fn test_megamorphic_site_keeps_missing() {
+    let emitted = run_and_inspect(
+        "
+        function x(point) {
+            return point.x;
+        }
+
+        var total = 0;
+        for (var i = 0; i < 2; i++) {
+            total += x({ x: 1 }) + x({ a: 0, x: 2 }) + x({ b: 0, x: 3 });
+            total += x({ c: 0, x: 4 }) + x({ d: 0, x: 5 });
+        }
+        __emit(total);
+        ",
+        |environment| {
+            let cache = environment
+                .inline_caches()
+                .find(|cache| cache.property() == "x")
+                .expect("no cache for x");
+
+            // the fifth shape doesn't fit
+            assert_eq!(cache.shapes().len(), 4);
+            assert_eq!((cache.hits(), cache.misses()), (4, 6));
+        },
+    );
+
+    assert_eq!(emitted, ["30"]);
+}
+
+#[test]
+fn test_store_site() {
+    let emitted = run_and_inspect(
+        "
+        function move(point, x) {
+            point.x = x;
+        }
+
+        var point = { x: 0 };
+        for (var i = 0; i < 5; i++) {
+            move(point, i);
+        }
+
+        var empty = {};
+        move(empty, 7);
+        move(1, 2);
+        __emit(point.x);
+        __emit(empty.x);
+        ",
+        |environment| {
+            // adding the property to the empty object and storing into a
+            // number both miss
+            assert_eq!(caches(environment, PropertyAccess::Store, "x"), [(4, 3)]);
+        },
+    );
+
+    assert_eq!(emitted, ["4", "7"]);
+}
+
+#[test]
+fn test_values_that_are_not_objects_miss() {
+    let emitted = run_and_inspect(
+        "
+        function length(value) {
+            return value.length;
+        }
+
+        __emit(length('abc'));
+        __emit(length({ length: 2 }));
+        __emit(length({ length: 5 }));
+        __emit(length(1));
+        ",
+        |environment| {
+            assert_eq!(
+                caches(environment, PropertyAccess::Load, "length"),
+                [(1, 3)]
+            );
+        },
+    );
+
+    assert_eq!(emitted, ["3", "2", "5", "undefined"]);
+}
+
+#[test]
+fn test_many_sites_in_one_block() {
+    // enough accesses for the block to take more than a page of code
+    let names: Vec<String> = (0..60).map(|index| format!("a{}", index)).collect();
+    let loads: Vec<String> = names.iter().map(|name| format!("o.{}", name)).collect();
+
+    let code = format!(
+        "
+        function sum(o) {{
+            return {};
+        }}
+
+        var o = {{}};
+        for (var i = 0; i < 60; i++) {{
+            o['a' + i] = i;
+        }}
+        __emit(sum(o));
+        __emit(sum(o));
+        ",
+        loads.join(" + ")
+    );
+
+    let emitted = run_and_inspect(&code, |environment| {
+        for name in &names {
+            assert_eq!(caches(environment, PropertyAccess::Load, name), [(1, 1)]);
+        }
+    });
+
+    assert_eq!(emitted, ["1770", "1770"]);
+}
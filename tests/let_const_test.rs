@@ -0,0 +1,203 @@
+mod common;
+
+use common::run;
+use omjit::{parse_program, FlowGraph, Scope};
+
+fn compile_error(code: &str) -> String {
+    let node = parse_program(code).expect("parse");
+    FlowGraph::from_root_node(&node, Scope::default())
+        .expect_err("compiled successfully")
+        .to_string()
+}
+
+#[test]
+fn test_block_scoped_bindings() {
+    let emitted = run("
+        let a = 1;
+        const b = 2;
+        {
+            let a = 10;
+            const b = 20;
+            __emit(a + b);
+        }
+        __emit(a + b);
+    ");
+
+    assert_eq!(emitted, ["30", "3"]);
+}
+
+#[test]
+fn test_loop_bindings_are_scoped_to_the_loop() {
+    let emitted = run("
+        let i = 10;
+        var sum = 0;
+        for (let i = 0; i < 3; i++) {
+            sum += i;
+        }
+        __emit(i);
+        __emit(sum);
+    ");
+
+    assert_eq!(emitted, ["10", "3"]);
+}
+
+#[test]
+fn test_closures_capture_block_bindings() {
+    let emitted = run("
+        var sum = 0;
+        outer: for (let i = 0; i < 4; i++) {
+            for (let j = 0; j < 4; j++) {
+                function read() {
+                    return i * 10 + j;
+                }
+                if (j == 1) continue outer;
+                if (i == 2) break outer;
+                sum += read();
+            }
+        }
+        {
+            let k = 100;
+            function add() {
+                return sum + k;
+            }
+            __emit(add());
+        }
+        __emit(sum);
+    ");
+
+    assert_eq!(emitted, ["110", "10"]);
+}
+
+#[test]
+fn test_closures_keep_the_bindings_of_their_iteration() {
+    let emitted = run("
+        var first;
+        var last;
+        for (let i = 0; i < 3; i++) {
+            let j = i * 10;
+            if (i == 0) first = function () { return i + j; };
+            last = function () { return i + j; };
+        }
+        __emit(first());
+        __emit(last());
+    ");
+
+    assert_eq!(emitted, ["0", "22"]);
+}
+
+#[test]
+fn test_per_iteration_bindings() {
+    let emitted = run("
+        var first = {};
+        var second = {};
+        for (let i = 0; i < 3; i++) {
+            let j = i * 10;
+            first[i] = function () { return i; };
+            second[i] = function () { return j; };
+        }
+        __emit(first[0]() + first[1]() + first[2]());
+        __emit(second[0]() + second[1]() + second[2]());
+    ");
+
+    assert_eq!(emitted, ["3", "30"]);
+}
+
+#[test]
+fn test_loop_bindings_across_break_and_continue() {
+    let emitted = run("
+        var f = {};
+        outer: for (let i = 0; i < 4; i++) {
+            let captured = function () { return i; };
+            for (let j = 0; j < 4; j++) {
+                let k = function () { return j; };
+                if (j == 1) continue outer;
+                if (i == 2) break outer;
+                f[i] = function () { return captured() * 10 + k(); };
+            }
+        }
+        __emit(f[0]());
+        __emit(f[1]());
+    ");
+
+    assert_eq!(emitted, ["0", "10"]);
+}
+
+#[test]
+fn test_closure_reads_binding_declared_later() {
+    let emitted = run("
+        function read() {
+            return later;
+        }
+        let later = 5;
+        __emit(read());
+    ");
+
+    assert_eq!(emitted, ["5"]);
+}
+
+#[test]
+fn test_let_without_initializer_is_undefined_each_time() {
+    let emitted = run("
+        for (var i = 0; i < 2; i++) {
+            let x;
+            __emit(x);
+            x = i;
+        }
+    ");
+
+    assert_eq!(emitted, ["undefined", "undefined"]);
+}
+
+#[test]
+fn test_const_properties_can_change() {
+    let emitted = run("
+        const o = { a: 1 };
+        o.a = 2;
+        __emit(o.a);
+    ");
+
+    assert_eq!(emitted, ["2"]);
+}
+
+#[test]
+fn test_assignment_to_const_is_a_compile_error() {
+    for code in [
+        "const c = 1; c = 2;",
+        "const c = 1; c += 2;",
+        "const c = 1; c++;",
+        "for (const i = 0; i < 2; i++) {}",
+    ] {
+        assert_eq!(
+            compile_error(code),
+            "TypeError: Assignment to constant variable.",
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+fn test_invalid_declarations() {
+    assert_eq!(
+        compile_error("const c;"),
+        "SyntaxError: Missing initializer in const declaration"
+    );
+    assert_eq!(
+        compile_error("if (1) let x = 1;"),
+        "SyntaxError: Lexical declaration cannot appear in a single-statement context"
+    );
+
+    for code in [
+        "let a; let a;",
+        "var a; const a = 1;",
+        "{ let a; function a() {} }",
+        "function f(a) { let a; }",
+    ] {
+        assert_eq!(
+            compile_error(code),
+            "SyntaxError: Identifier 'a' has already been declared",
+            "{}",
+            code
+        );
+    }
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_boolean_literals() {
+    let emitted = run("
+        function flip(value) {
+            return !value;
+        }
+
+        var yes = true;
+        __emit(yes);
+        __emit(flip(true));
+        __emit(false ? 1 : 2);
+        __emit(yes && false);
+        __emit(true + true);
+        __emit(true == 1);
+        __emit(true === 1);
+        __emit(false < true);
+        __emit(typeof false);
+    ");
+
+    assert_eq!(
+        emitted,
+        ["true", "false", "2", "false", "2", "true", "false", "true", "boolean"]
+    );
+}
+
+#[test]
+fn test_null_literal() {
+    let emitted = run("
+        function nothing() {
+            return null;
+        }
+
+        var missing;
+        var empty = null;
+        __emit(empty);
+        __emit(nothing());
+        __emit(null ? 1 : 2);
+        __emit(!null);
+        __emit(null + 1);
+        __emit(typeof null);
+        __emit(null == missing);
+        __emit(null === missing);
+        __emit(nothing() == null);
+        __emit(nothing() == 0);
+        __emit(empty === nothing());
+    ");
+
+    assert_eq!(
+        emitted,
+        ["null", "null", "2", "true", "1", "object", "true", "false", "true", "false", "true"]
+    );
+}
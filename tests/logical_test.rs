@@ -0,0 +1,93 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_logical_operators_return_an_operand() {
+    let emitted = run("
+        __emit(1 && 2);
+        __emit(0 && 2);
+        __emit(1 || 2);
+        __emit(0 || 2);
+        __emit((1 < 2) && 5);
+        __emit((1 > 2) && 5);
+        __emit((1 > 2) || 'fallback');
+        __emit(0 / 0 || void 0);
+    ");
+
+    assert_eq!(
+        emitted,
+        ["2", "0", "1", "2", "5", "false", "fallback", "undefined"]
+    );
+}
+
+#[test]
+fn test_logical_operators_short_circuit() {
+    let emitted = run("
+        function side_effect(x) {
+            __emit(x);
+            return x;
+        }
+
+        __emit(side_effect(0) && side_effect(1));
+        __emit(side_effect(2) || side_effect(3));
+        __emit(side_effect(4) && side_effect(5) && side_effect(6));
+    ");
+
+    assert_eq!(emitted, ["0", "0", "2", "2", "4", "5", "6", "6"]);
+}
+
+#[test]
+fn test_logical_operators_in_conditions() {
+    let emitted = run("
+        function in_range(x, low, high) {
+            if (x >= low && x <= high) {
+                return 1;
+            }
+            return 0;
+        }
+
+        function either_zero(a, b) {
+            var count = 0;
+            while (a == 0 || b == 0) {
+                return 1;
+            }
+            return 0;
+        }
+
+        __emit(in_range(5, 1, 10));
+        __emit(in_range(0, 1, 10));
+        __emit(in_range(11, 1, 10));
+        __emit(either_zero(0, 1));
+        __emit(either_zero(1, 0));
+        __emit(either_zero(1, 1));
+    ");
+
+    assert_eq!(emitted, ["1", "0", "0", "1", "1", "0"]);
+}
+
+#[test]
+fn test_nullish_coalescing() {
+    let emitted = run("
+        function side_effect(x) {
+            __emit(x);
+            return x;
+        }
+
+        var missing;
+        __emit(missing ?? 'default');
+        __emit(null ?? 1);
+        __emit(0 ?? 1);
+        __emit('' ?? 1);
+        __emit(false ?? 1);
+        __emit(missing ?? null ?? 2);
+        __emit(1 ?? side_effect(3));
+        __emit(null ?? side_effect(4));
+        __emit(1 + (missing ?? 5) * 2);
+    ");
+
+    assert_eq!(
+        emitted,
+        ["default", "1", "0", "", "false", "2", "1", "4", "4", "11"]
+    );
+}
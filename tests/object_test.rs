@@ -0,0 +1,205 @@
+mod common;
+
+use common::run;
+use omjit::ShapeId;
+
+#[test]
+fn test_shape_transitions() {
+    let xy = ShapeId::EMPTY.with_property("x").with_property("y");
+    let yx = ShapeId::EMPTY.with_property("y").with_property("x");
+
+    assert_eq!(xy, ShapeId::EMPTY.with_property("x").with_property("y"));
+    assert_ne!(xy, yx);
+    assert_eq!(xy.with_property("x"), xy);
+    assert_eq!((xy.slot("x"), xy.slot("y")), (Some(0), Some(1)));
+    assert_eq!((yx.slot("x"), yx.slot("y")), (Some(1), Some(0)));
+    assert_eq!(xy.slot("z"), None);
+}
+
+#[test]
+fn test_object_literals() {
+    let emitted = run("
+        var point = { x: 1, 'y': 2, 3: 'three' };
+        __emit(point.x + point.y);
+        __emit(point[3]);
+        __emit(point.z);
+        __emit({ a: 1, a: 2 }.a);
+        __emit({ first: 'known' }.first);
+        __emit(typeof point);
+        __emit('' + point);
+        __emit(point ? 'truthy' : 'falsy');
+    ");
+
+    assert_eq!(
+        emitted,
+        [
+            "3",
+            "three",
+            "undefined",
+            "2",
+            "known",
+            "object",
+            "[object Object]",
+            "truthy"
+        ]
+    );
+}
+
+#[test]
+fn test_setting_and_adding_properties() {
+    let emitted = run("
+        var counter = { count: 0 };
+        counter.count = counter.count + 1;
+        counter.count += 10;
+        counter.count++;
+        ++counter.count;
+        __emit(counter.count);
+
+        counter.label = 'clicks';
+        counter['suffix'] = '!';
+        __emit(counter.label + ': ' + counter.count + counter.suffix);
+
+        var copy = counter;
+        copy.count = 0;
+        __emit(counter.count);
+        __emit(copy === counter);
+        __emit({} === {});
+    ");
+
+    assert_eq!(emitted, ["13", "clicks: 13!", "0", "true", "false"]);
+}
+
+#[test]
+fn test_computed_keys() {
+    let emitted = run("
+        var table = {};
+        for (var i = 0; i < 5; i++) {
+            table['square' + i] = i * i;
+            table[i] = -i;
+        }
+
+        var key = 'square';
+        __emit(table[key + 3]);
+        __emit(table[4]);
+        __emit(table['4']);
+        __emit(table[key]);
+    ");
+
+    assert_eq!(emitted, ["9", "-4", "-4", "undefined"]);
+}
+
+#[test]
+fn test_objects_through_functions() {
+    let emitted = run("
+        function make(x, y) {
+            return { x: x, y: y, next: null };
+        }
+
+        function length(list) {
+            var count = 0;
+            while (list) {
+                count++;
+                list = list.next;
+            }
+            return count;
+        }
+
+        var list = make(1, 2);
+        list.next = make(3, 4);
+        list.next.next = make(5, 6);
+
+        __emit(length(list));
+        __emit(list.next.next.x * list.next.y);
+
+        var methods = { double: function (n) { return n * 2; } };
+        __emit(methods.double(21));
+    ");
+
+    assert_eq!(emitted, ["3", "20", "42"]);
+}
+
+#[test]
+fn test_compound_assignment_evaluates_target_once() {
+    let emitted = run("
+        var calls = 0;
+        var o = { a: 1 };
+        function get() {
+            calls++;
+            return o;
+        }
+
+        get().a += 1;
+        get().a++;
+        ++get().a;
+        __emit(calls);
+        __emit(o.a);
+        __emit(get().a--);
+        __emit(o.a);
+
+        var t2 = { 0: 1, 1: 10 }, j = 0;
+        t2[j++] += 100;
+        __emit(j);
+        __emit(t2[0]);
+        __emit(t2[1]);
+
+        var keys = 0;
+        function key() {
+            keys++;
+            return 'b';
+        }
+        var p = { b: '1' };
+        __emit(p[key()]++);
+        __emit(p[key()] *= 3);
+        __emit(keys);
+        __emit(p.b);
+    ");
+
+    assert_eq!(
+        emitted,
+        ["3", "4", "4", "3", "1", "101", "10", "1", "6", "2", "6"]
+    );
+}
+
+#[test]
+fn test_assignment_evaluates_target_before_value() {
+    let emitted = run("
+        var order = '';
+        var o = {};
+        function target() {
+            order += 't';
+            return o;
+        }
+        function value() {
+            order += 'v';
+            return 1;
+        }
+
+        target().a = value();
+        target()['b'] = value();
+        __emit(order);
+        __emit(o.a + o.b);
+    ");
+
+    assert_eq!(emitted, ["tvtv", "2"]);
+}
+
+#[test]
+fn test_object_literal_with_many_properties() {
+    let emitted = run("
+        var o = { a: 1, b: 2, c: 3, d: 4, e: 5, f: 6, g: 7, h: 8, i: 9, j: { k: 10, l: 11 } };
+        __emit(o.a + o.b + o.c + o.d + o.e + o.f + o.g + o.h + o.i + o.j.k + o.j.l);
+    ");
+
+    assert_eq!(emitted, ["66"]);
+}
+
+#[test]
+fn test_nested_object_literals() {
+    let emitted = run("
+        var o = { a: { b: { c: { d: 1 } } }, e: { f: { g: { h: { i: 2 } } } } };
+        __emit(o.a.b.c.d + o.e.f.g.h.i);
+        __emit({ x: { y: 3 }, z: 4 }.x.y);
+    ");
+
+    assert_eq!(emitted, ["3", "3"]);
+}
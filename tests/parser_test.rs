@@ -0,0 +1,27 @@
+use omjit::parse_program;
+
+#[test]
+fn test_unsupported_declarations_are_syntax_errors() {
+    for code in ["class A {}", "var a = 1;\n\nclass B {}\na;"] {
+        let error = parse_program(code).expect_err("parsed successfully");
+        assert!(
+            error
+                .to_string()
+                .starts_with("SyntaxError: Unsupported syntax"),
+            "unexpected error: {}",
+            error
+        );
+    }
+
+    let error = parse_program("var a = 1;\n\nclass B {}\na;").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "SyntaxError: Unsupported syntax on line 3: class B {}"
+    );
+}
+
+#[test]
+fn test_complete_programs_parse() {
+    assert!(parse_program("var a = 1;\nfunction f(x) { return x; }\n").is_ok());
+    assert!(parse_program("let a = 1;\nconst b = 2;\nfor (let i = 0; i < b; i++) {}\n").is_ok());
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_return_from_a_loop() {
+    let emitted = run("
+        function find(limit) {
+            var total = 0;
+            for (var i = 0; i < 100; i++) {
+                total += i;
+                if (total >= limit) {
+                    return i;
+                }
+            }
+            return -1;
+        }
+
+        __emit(find(10));
+        __emit(find(100000));
+    ");
+
+    assert_eq!(emitted, ["4", "-1"]);
+}
+
+#[test]
+fn test_return_skips_the_following_statements() {
+    let emitted = run("
+        function check(x) {
+            if (x > 0) {
+                __emit(1);
+                return 10;
+                __emit(2);
+            }
+            __emit(3);
+            return 20;
+        }
+
+        __emit(check(1));
+        __emit(check(-1));
+    ");
+
+    assert_eq!(emitted, ["1", "10", "3", "20"]);
+}
+
+#[test]
+fn test_return_from_nested_loops() {
+    let emitted = run("
+        function pair(target) {
+            var i = 0;
+            while (i < 10) {
+                for (var j = 0; j < 10; j++) {
+                    if (i * 10 + j == target) {
+                        return i * 100 + j;
+                    }
+                }
+                i++;
+            }
+        }
+
+        __emit(pair(42));
+        __emit(pair(1000));
+    ");
+
+    assert_eq!(emitted, ["402", "undefined"]);
+}
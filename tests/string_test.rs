@@ -0,0 +1,86 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_concatenation() {
+    let emitted = run("
+        var greeting = 'Hello, ' + 'world';
+        __emit(greeting + '!');
+        __emit('n = ' + 42);
+        __emit(1.5 + ' and ' + -0.25);
+        __emit(1 + 2 + '3');
+        __emit('1' + 2 + 3);
+        __emit('' + (1 < 2) + ' ' + 1e21);
+    ");
+
+    assert_eq!(
+        emitted,
+        [
+            "Hello, world!",
+            "n = 42",
+            "1.5 and -0.25",
+            "33",
+            "123",
+            "true 1e+21"
+        ]
+    );
+}
+
+#[test]
+fn test_concatenating_dynamic_values() {
+    let emitted = run("
+        function pad(text, width) {
+            while (text.length < width) {
+                text = ' ' + text;
+            }
+            return text;
+        }
+
+        var line = '';
+        for (var i = 1; i <= 3; i++) {
+            line += pad('' + i * 5, 3);
+        }
+
+        var missing;
+        __emit(line);
+        __emit(pad('x', 0) + missing);
+        __emit(line + 1 === '  5 10 151');
+    ");
+
+    assert_eq!(emitted, ["  5 10 15", "xundefined", "true"]);
+}
+
+#[test]
+fn test_length() {
+    let emitted = run("
+        var empty = '';
+        function name() {
+            return 'omjit';
+        }
+
+        __emit('abc'.length);
+        __emit(empty.length);
+        __emit(name().length * 2);
+        __emit((name() + name())['length']);
+        __emit('héllo'.length);
+        __emit((1).length);
+    ");
+
+    assert_eq!(emitted, ["3", "0", "10", "10", "5", "undefined"]);
+}
+
+#[test]
+fn test_comparing_strings() {
+    let emitted = run("
+        var a = 'ab' + 'c';
+        __emit(a == 'abc');
+        __emit(a === 'ab' + 'c');
+        __emit(a != 'abd');
+        __emit('abc' < 'abd');
+        __emit('B' < 'a');
+        __emit('10' < '9');
+    ");
+
+    assert_eq!(emitted, ["true", "true", "true", "true", "true", "true"]);
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_unary_operators() {
+    let emitted = run("
+        __emit(-5);
+        __emit(-(0 - 2.5));
+        __emit(1 / -0);
+        __emit(+(1 < 2));
+        __emit(-(1 > 2));
+        __emit(~5);
+        __emit(~(0 - 1));
+        __emit(~2.7);
+        __emit(~(0 / 0));
+        __emit(!0);
+        __emit(!(0 / 0));
+        __emit(!3);
+        __emit(!(1 < 2));
+        __emit(!!(1 < 2));
+        __emit(void 7);
+        __emit(+void 7);
+    ");
+
+    assert_eq!(
+        emitted,
+        [
+            "-5",
+            "2.5",
+            "-Infinity",
+            "1",
+            "0",
+            "-6",
+            "0",
+            "-3",
+            "-1",
+            "true",
+            "true",
+            "false",
+            "false",
+            "true",
+            "undefined",
+            "NaN"
+        ]
+    );
+}
+
+#[test]
+fn test_typeof() {
+    let emitted = run("
+        var n = 1;
+        __emit(typeof 1);
+        __emit(typeof n);
+        __emit(typeof (1 < 2));
+        __emit(typeof 'text');
+        __emit(typeof void 0);
+        __emit(typeof missing);
+        __emit(typeof n == 'number');
+        __emit(typeof n === 'string');
+    ");
+
+    assert_eq!(
+        emitted,
+        [
+            "number",
+            "number",
+            "boolean",
+            "string",
+            "undefined",
+            "undefined",
+            "true",
+            "false"
+        ]
+    );
+}
+
+#[test]
+fn test_update_expressions() {
+    let emitted = run("
+        var i = 5;
+        __emit(i++);
+        __emit(i);
+        __emit(++i);
+        __emit(i--);
+        __emit(--i);
+        __emit(i);
+
+        function bump(x) {
+            x++;
+            return ++x;
+        }
+        __emit(bump(1));
+    ");
+
+    assert_eq!(emitted, ["5", "6", "7", "7", "5", "5", "3"]);
+}
+
+#[test]
+fn test_update_in_loops() {
+    let emitted = run("
+        function count(n) {
+            var total = 0;
+            for (var i = 0; i < n; i++) {
+                total++;
+            }
+            return total;
+        }
+
+        __emit(count(4));
+    ");
+
+    assert_eq!(emitted, ["4"]);
+}
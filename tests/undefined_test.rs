@@ -0,0 +1,122 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_uninitialised_bindings_are_undefined() {
+    let emitted = run("
+        var x;
+        __emit(x);
+        __emit(x + 1);
+        __emit(+x);
+        __emit(x ? 1 : 2);
+
+        function missing(a, b) {
+            return b;
+        }
+        __emit(missing(1));
+    ");
+
+    assert_eq!(emitted, ["undefined", "NaN", "NaN", "2", "undefined"]);
+}
+
+#[test]
+fn test_bare_return_gives_undefined() {
+    let emitted = run("
+        function check(x) {
+            if (x) {
+                return;
+            }
+            return 1;
+        }
+
+        __emit(check(1));
+        __emit(check(0));
+    ");
+
+    assert_eq!(emitted, ["undefined", "1"]);
+}
+
+#[test]
+fn test_falling_off_the_end_gives_undefined() {
+    let emitted = run("
+        var calls = 0;
+
+        function nothing() {
+        }
+
+        function side_effect() {
+            for (var i = 0; i < 3; i++) {
+                calls++;
+            }
+        }
+
+        __emit(nothing());
+        __emit(side_effect());
+        __emit(calls);
+    ");
+
+    assert_eq!(emitted, ["undefined", "undefined", "3"]);
+}
+
+#[test]
+fn test_undefined_identifier() {
+    let emitted = run("
+        var x;
+        __emit(x === undefined);
+        __emit(undefined);
+        __emit(typeof undefined);
+        __emit(null == undefined);
+        __emit(0 === undefined);
+
+        function check(value) {
+            return value === undefined ? 'missing' : value;
+        }
+        __emit(check());
+        __emit(check(1));
+    ");
+
+    assert_eq!(
+        emitted,
+        [
+            "true",
+            "undefined",
+            "undefined",
+            "true",
+            "false",
+            "missing",
+            "1"
+        ]
+    );
+}
+
+#[test]
+fn test_assigning_to_undefined_does_nothing() {
+    let emitted = run("
+        var calls = 0;
+        function next() {
+            calls++;
+            return calls;
+        }
+
+        var undefined = next();
+        __emit(undefined);
+        __emit(undefined = next());
+        __emit(undefined);
+        undefined += 1;
+        undefined++;
+        __emit(undefined);
+        __emit(calls);
+
+        function shadowed() {
+            var undefined = 4;
+            return undefined;
+        }
+        __emit(shadowed());
+    ");
+
+    assert_eq!(
+        emitted,
+        ["undefined", "2", "undefined", "undefined", "2", "4"]
+    );
+}